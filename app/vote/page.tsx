@@ -4,11 +4,18 @@ import { useAnchorWallet, useConnection } from "@solana/wallet-adapter-react";
 import { useState, useEffect } from "react";
 import { Vote, Users, Plus, Check, X, ShieldCheck, Mic2, Music2, Activity, Volume2, VolumeX, Radio } from "lucide-react";
 import { AnchorProvider, setProvider, web3, BN } from "@coral-xyz/anchor";
-import { getGovProgram } from "@/lib/solana-client";
+import { getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { getGovProgram, govPdas, GOV_DOMAINS, GRIT_MINT } from "@/lib/solana-client";
 import Navigation from "@/components/Navigation";
 import { motion } from "framer-motion";
 import { useGritState } from "@/components/GritStateProvider";
 
+// Mix proposals carry their text inline as a `data:` content URI.
+const TEXT_URI_PREFIX = "data:text/plain,";
+
+const proposalText = (contentUri: string) =>
+    contentUri.startsWith(TEXT_URI_PREFIX) ? decodeURIComponent(contentUri.slice(TEXT_URI_PREFIX.length)) : contentUri;
+
 const STEMS = [
     { id: 'vox1', label: 'Lead Vox', icon: Mic2, color: 'text-pink-400', bg: 'bg-pink-500/10' },
     { id: 'inout', label: 'In/Out', icon: Activity, color: 'text-cyan-400', bg: 'bg-cyan-500/10' },
@@ -79,15 +86,41 @@ export default function GovernancePage() {
             const provider = new AnchorProvider(connection, wallet, {});
             const program = getGovProgram(provider);
 
-            const proposalKp = web3.Keypair.generate();
+            const realm = govPdas.realm(GRIT_MINT);
+            const realmAccount = await program.account.realm.fetch(realm);
+            const proposal = govPdas.proposal(realm, realmAccount.proposalCount as BN);
+            const domainConfig = govPdas.domainConfig(realm, GOV_DOMAINS.indexOf("sovereign"));
+            const stakePool = govPdas.stakePool(GRIT_MINT);
+
+            const contentUri = TEXT_URI_PREFIX + encodeURIComponent(desc);
+            const digest = await crypto.subtle.digest("SHA-256", new TextEncoder().encode(desc));
+            const contentHash = Array.from(new Uint8Array(digest));
+
+            // Proposals start as drafts; open voting in the same transaction.
+            const openVoting = await program.methods.openVoting()
+                .accounts({
+                    realm,
+                    proposal,
+                    domainConfig,
+                    stakePool,
+                    poolHistory: govPdas.history(stakePool),
+                    author: wallet.publicKey,
+                })
+                .instruction();
 
-            await program.methods.createProposal(title, desc)
+            await program.methods.createProposal(title, contentUri, contentHash, { sovereign: {} }, { binary: {} }, [])
                 .accounts({
-                    proposal: proposalKp.publicKey,
+                    realm,
+                    proposal,
+                    domainConfig,
+                    ringProgress: null,
+                    depositVault: govPdas.depositVault(realm),
+                    authorTokenAccount: getAssociatedTokenAddressSync(GRIT_MINT, wallet.publicKey),
                     author: wallet.publicKey,
+                    tokenProgram: TOKEN_PROGRAM_ID,
                     systemProgram: web3.SystemProgram.programId,
                 })
-                .signers([proposalKp])
+                .postInstructions([openVoting])
                 .rpc();
 
             alert("Proposal Created!");
@@ -100,7 +133,7 @@ export default function GovernancePage() {
         }
     };
 
-    const handleVote = async (proposalPk: web3.PublicKey, approve: boolean) => {
+    const handleVote = async (item: { publicKey: web3.PublicKey; account: any }, approve: boolean) => {
         if (!wallet) return alert("Connect wallet!");
         if (!CAN_VOTE) return alert(`Resonance Rank too low. You are an "${resonanceRank}". Reach "Signal" rank by listening to music to vote.`);
 
@@ -108,16 +141,23 @@ export default function GovernancePage() {
             const provider = new AnchorProvider(connection, wallet, {});
             const program = getGovProgram(provider);
 
-            const voteRecord = web3.Keypair.generate();
+            const p = item.account;
+            const realmAccount = await program.account.realm.fetch(p.realm);
+            const stakePool = govPdas.stakePool(realmAccount.governingMint as web3.PublicKey);
+            const domain = GOV_DOMAINS.indexOf(Object.keys(p.domain)[0] as (typeof GOV_DOMAINS)[number]);
 
-            await program.methods.castVote(new BN(0), approve)
+            await program.methods.castVote(approve)
                 .accounts({
-                    proposal: proposalPk,
-                    voteRecord: voteRecord.publicKey,
+                    realm: p.realm,
+                    proposal: item.publicKey,
+                    domainConfig: govPdas.domainConfig(p.realm, domain),
+                    ringProgress: null,
+                    voteRecord: govPdas.voteRecord(item.publicKey, wallet.publicKey),
+                    stakePool,
+                    voterHistory: govPdas.history(stakePool, wallet.publicKey),
                     voter: wallet.publicKey,
                     systemProgram: web3.SystemProgram.programId
                 })
-                .signers([voteRecord])
                 .rpc();
 
             alert(`Voted ${approve ? "YES" : "NO"}!`);
@@ -281,7 +321,7 @@ export default function GovernancePage() {
                                         <div className="flex flex-col md:flex-row md:items-start justify-between mb-8 gap-4">
                                             <div>
                                                 <h3 className="text-2xl font-bold text-white mb-2 leading-tight group-hover:text-indigo-300 transition-colors">{p.title}</h3>
-                                                <p className="text-gray-400 leading-relaxed max-w-xl">{p.description}</p>
+                                                <p className="text-gray-400 leading-relaxed max-w-xl">{proposalText(p.contentUri)}</p>
                                             </div>
                                             <div className="shrink-0">
                                                 <div className="inline-flex items-center gap-2 bg-green-500/10 text-green-400 px-3 py-1.5 rounded-full text-xs font-bold border border-green-500/20 uppercase tracking-wider">
@@ -317,13 +357,13 @@ export default function GovernancePage() {
 
                                         <div className="flex flex-col sm:flex-row gap-4 pt-6 border-t border-white/5">
                                             <button
-                                                onClick={() => handleVote(item.publicKey, true)}
+                                                onClick={() => handleVote(item, true)}
                                                 className="flex-1 py-3 px-6 rounded-xl bg-gray-800 hover:bg-green-500 hover:text-black hover:border-green-400 text-white font-bold transition-all border border-white/5 flex items-center justify-center gap-2"
                                             >
                                                 <Check className="w-4 h-4" /> Vote For
                                             </button>
                                             <button
-                                                onClick={() => handleVote(item.publicKey, false)}
+                                                onClick={() => handleVote(item, false)}
                                                 className="flex-1 py-3 px-6 rounded-xl bg-gray-800 hover:bg-red-500 hover:text-black hover:border-red-400 text-white font-bold transition-all border border-white/5 flex items-center justify-center gap-2"
                                             >
                                                 <X className="w-4 h-4" /> Vote Against
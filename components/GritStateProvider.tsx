@@ -605,18 +605,24 @@ export function GritStateProvider({ children }: { children: ReactNode }) {
                         new Promise((_, reject) => setTimeout(() => reject(new Error("RPC Timeout")), 30000))
                     ]) as any[];
 
+                    // Anchor decodes the status enum as `{ active: {} }`; on-chain `Failed` is "Rejected" here.
+                    const onChainStatus = (status: object | undefined): Proposal["status"] => {
+                        const name = Object.keys(status ?? { active: {} })[0];
+                        return name === "failed" ? "Rejected" : (name[0].toUpperCase() + name.slice(1)) as Proposal["status"];
+                    };
+
                     const mappedProposals: Proposal[] = allProposals.map(p => {
                         const acc = p.account as any;
                         return {
                             id: p.publicKey.toBase58(),
                             title: acc.title,
-                            description: acc.description,
+                            description: acc.contentUri,
                             author: acc.author.toBase58(),
                             votesFor: acc.votesFor?.toNumber?.() || 0,
                             votesAgainst: acc.votesAgainst?.toNumber?.() || 0,
-                            createdAt: (acc.createdAt?.toNumber?.() || Date.now() / 1000) * 1000,
-                            status: "Active",
-                            deadline: ((acc.createdAt?.toNumber?.() || Date.now() / 1000) * 1000) + (3 * 24 * 60 * 60 * 1000),
+                            createdAt: (acc.creationTime?.toNumber?.() || Date.now() / 1000) * 1000,
+                            status: onChainStatus(acc.status),
+                            deadline: (acc.deadline?.toNumber?.() || 0) * 1000,
                             domain: "SOVEREIGN",
                             resonance: {
                                 amplify: acc.votesFor?.toNumber?.() || 0,
@@ -1,80 +1,861 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use grit_staking::{RingProgress, StakeHistory, StakePool, StakingConfig, MAX_VOTING_PERIOD};
 
 declare_id!("AotidXSUcQsaQHbkwwrrnCX9MiMYhu9JimPA2LJ2VSxj");
 
+/// Basis-point denominator for quorum and pass thresholds.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Proposal title limit, in bytes.
+pub const MAX_TITLE_LEN: usize = 64;
+
+/// Proposal content URI limit, in bytes.
+pub const MAX_URI_LEN: usize = 200;
+
+/// Largest council a domain can restrict voting to.
+pub const MAX_COUNCIL: usize = 16;
+
+/// Most options a multiple-choice proposal can offer.
+pub const MAX_OPTIONS: usize = 8;
+
+/// Option label limit, in bytes.
+pub const MAX_OPTION_LEN: usize = 32;
+
+/// Ballots counted per `tally_ranked_votes` call, to stay within compute limits.
+pub const MAX_TALLY_BATCH: usize = 10;
+
 #[program]
 pub mod grit_gov {
     use super::*;
 
-    pub fn initialize(_ctx: Context<Initialize>) -> Result<()> {
+    /// Creates the realm for `governing_mint`, whose staking pool supplies voting power.
+    /// Forfeited proposal deposits go to `treasury`.
+    pub fn initialize(ctx: Context<Initialize>, config: GovConfig) -> Result<()> {
+        config.validate()?;
+        let realm = &mut ctx.accounts.realm;
+        realm.admin = ctx.accounts.admin.key();
+        realm.governing_mint = ctx.accounts.governing_mint.key();
+        realm.config = config;
+        realm.proposal_count = 0;
+        realm.bump = ctx.bumps.realm;
+        realm.treasury = ctx.accounts.treasury.key();
+        Ok(())
+    }
+
+    /// Changes the rules for new proposals; open proposals keep the rules they were created with.
+    pub fn set_gov_config(ctx: Context<SetGovConfig>, config: GovConfig) -> Result<()> {
+        config.validate()?;
+        ctx.accounts.realm.config = config;
         Ok(())
     }
 
-    pub fn create_proposal(ctx: Context<CreateProposal>, title: String, description: String) -> Result<()> {
+    pub fn set_treasury(ctx: Context<SetTreasury>) -> Result<()> {
+        ctx.accounts.realm.treasury = ctx.accounts.treasury.key();
+        Ok(())
+    }
+
+    /// Sets who may propose and vote in `domain`, and optionally a quorum that
+    /// replaces the realm's. Each domain must be configured before it takes proposals.
+    pub fn set_domain_config(
+        ctx: Context<SetDomainConfig>,
+        domain: Domain,
+        quorum: Option<Quorum>,
+        min_ring: u8,
+        council: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(council.len() <= MAX_COUNCIL, ErrorCode::CouncilTooLarge);
+        if let Some(Quorum::SupplyBps(bps)) = quorum {
+            require!(bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+        }
+
+        let domain_config = &mut ctx.accounts.domain_config;
+        domain_config.realm = ctx.accounts.realm.key();
+        domain_config.domain = domain;
+        domain_config.quorum = quorum;
+        domain_config.min_ring = min_ring;
+        domain_config.council = council;
+        domain_config.bump = ctx.bumps.domain_config;
+        Ok(())
+    }
+
+    /// Drafts a proposal in `domain` at the realm's next id. The full text lives off-chain
+    /// at `content_uri`, committed to by its SHA-256 `content_hash`. The author must meet
+    /// the domain's eligibility rules.
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        title: String,
+        content_uri: String,
+        content_hash: [u8; 32],
+        domain: Domain,
+        vote_type: VoteType,
+        options: Vec<String>,
+    ) -> Result<()> {
+        validate_metadata(&title, &content_uri)?;
+        validate_options(vote_type, &options)?;
+        let domain_config = &ctx.accounts.domain_config;
+        domain_config.check_eligible(&ctx.accounts.author.key(), ctx.accounts.ring_progress.as_deref())?;
+
+        let realm = &mut ctx.accounts.realm;
         let proposal = &mut ctx.accounts.proposal;
+        proposal.realm = realm.key();
+        proposal.id = realm.proposal_count;
+        proposal.bump = ctx.bumps.proposal;
+        proposal.domain = domain;
         proposal.author = ctx.accounts.author.key();
         proposal.title = title;
-        proposal.description = description;
+        proposal.content_uri = content_uri;
+        proposal.content_hash = content_hash;
         proposal.votes_for = 0;
         proposal.votes_against = 0;
-        proposal.status = ProposalStatus::Active;
+        proposal.vote_type = vote_type;
+        proposal.option_votes = vec![0; options.len()];
+        proposal.options = options;
+        proposal.status = ProposalStatus::Draft;
         proposal.creation_time = Clock::get()?.unix_timestamp;
+        proposal.deposit = realm.config.proposal_deposit;
+        realm.proposal_count = realm.proposal_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        if proposal.deposit > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.author_token_account.to_account_info(),
+                to: ctx.accounts.deposit_vault.to_account_info(),
+                authority: ctx.accounts.author.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), proposal.deposit)?;
+        }
         Ok(())
     }
 
-    pub fn cast_vote(ctx: Context<CastVote>, _proposal_id: u64, approve: bool) -> Result<()> {
+    /// Starts voting on a draft. The realm's and domain's current rules are fixed onto
+    /// the proposal here, so later config changes do not affect it.
+    pub fn open_voting(ctx: Context<OpenVoting>) -> Result<()> {
+        let config = &ctx.accounts.realm.config;
+        let clock = Clock::get()?;
+        // The slot before opening, so stake moved in the opening slot does not count.
+        // Quorum and vote weights both read the staking histories at this slot.
+        let snapshot_slot = clock.slot.saturating_sub(1);
+        let staked_power = ctx.accounts.pool_history.weight_at(snapshot_slot);
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.transition(ProposalAction::OpenVoting, clock.unix_timestamp)?;
+        proposal.snapshot_slot = snapshot_slot;
+        proposal.deadline = clock.unix_timestamp.checked_add(config.voting_period).ok_or(ErrorCode::MathOverflow)?;
+        proposal.quorum = ctx.accounts.domain_config.quorum.unwrap_or(config.quorum).required_votes(staked_power)?;
+        proposal.pass_threshold_bps = config.pass_threshold_bps;
+        proposal.execute_after = proposal.deadline.checked_add(config.timelock).ok_or(ErrorCode::MathOverflow)?;
+        proposal.refund_turnout = config.refund_turnout.required_votes(staked_power)?;
+        Ok(())
+    }
+
+    /// Withdraws a proposal. Allowed for drafts and for open proposals nobody has voted on.
+    /// A cancelled draft forfeits its deposit; see `Proposal::deposit_refundable`.
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.transition(ProposalAction::Cancel, Clock::get()?.unix_timestamp)
+    }
+
+    /// Blocks a passed proposal during its timelock. The realm guardian or any council
+    /// member of the proposal's domain may veto.
+    pub fn veto_proposal(ctx: Context<VetoProposal>) -> Result<()> {
+        let vetoer = ctx.accounts.vetoer.key();
+        require!(
+            vetoer == ctx.accounts.realm.guardian || ctx.accounts.domain_config.council.contains(&vetoer),
+            ErrorCode::Unauthorized
+        );
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.transition(ProposalAction::Veto, Clock::get()?.unix_timestamp)?;
+        msg!("Proposal {} vetoed by {}", proposal.key(), vetoer);
+        Ok(())
+    }
+
+    /// Pays out a finished proposal's deposit: back to the author, or to the realm
+    /// treasury if the proposal was vetoed or drew less than the refund turnout.
+    /// Anyone may call this.
+    pub fn settle_deposit(ctx: Context<SettleDeposit>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let refund = proposal.deposit_refundable(Clock::get()?.unix_timestamp)?;
+        let amount = proposal.deposit;
+        proposal.deposit = 0;
+
+        let realm = &ctx.accounts.realm;
+        let governing_mint = realm.governing_mint;
+        let bump = [realm.bump];
+        let seeds: &[&[u8]] = &[b"realm", governing_mint.as_ref(), &bump];
+        let signer = &[seeds];
+        let to = if refund {
+            ctx.accounts.author_token_account.to_account_info()
+        } else {
+            ctx.accounts.treasury.to_account_info()
+        };
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.deposit_vault.to_account_info(),
+            to,
+            authority: realm.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        emit!(DepositSettled {
+            proposal: proposal.key(),
+            author: proposal.author,
+            amount,
+            refunded: refund,
+        });
+        Ok(())
+    }
+
+    pub fn set_guardian(ctx: Context<SetGovConfig>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.realm.guardian = guardian;
+        Ok(())
+    }
+
+    /// Replaces the proposal's title and content while it is a draft.
+    pub fn update_proposal_metadata(
+        ctx: Context<UpdateProposalMetadata>,
+        title: String,
+        content_uri: String,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        validate_metadata(&title, &content_uri)?;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Draft, ErrorCode::ProposalNotDraft);
+
+        proposal.title = title;
+        proposal.content_uri = content_uri;
+        proposal.content_hash = content_hash;
+
+        emit!(ProposalMetadataUpdated {
+            proposal: proposal.key(),
+            title: proposal.title.clone(),
+            content_uri: proposal.content_uri.clone(),
+            content_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Attaches the next batch of instructions for the proposal to run if it passes.
+    /// Only the author may add them, and only while the proposal is a draft.
+    pub fn add_proposal_transaction(
+        ctx: Context<AddProposalTransaction>,
+        instructions: Vec<ProposalInstruction>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.status == ProposalStatus::Draft, ErrorCode::ProposalNotDraft);
+        require!(proposal.vote_type == VoteType::Binary, ErrorCode::WrongVoteType);
+        require!(!instructions.is_empty(), ErrorCode::EmptyTransaction);
+
+        let transaction = &mut ctx.accounts.proposal_transaction;
+        transaction.proposal = proposal.key();
+        transaction.index = proposal.transaction_count;
+        transaction.instructions = instructions;
+        transaction.bump = ctx.bumps.proposal_transaction;
+        proposal.transaction_count = proposal.transaction_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Runs the proposal's next transaction with the realm's governance PDA as signer.
+    /// The accounts each instruction needs, including its program, are passed as
+    /// remaining accounts. The proposal becomes `Executed` after its last transaction.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let transaction = &mut ctx.accounts.proposal_transaction;
+        require!(transaction.index == proposal.executed_count, ErrorCode::TransactionOutOfOrder);
+        proposal.transition(ProposalAction::Execute, Clock::get()?.unix_timestamp)?;
+
+        let realm = ctx.accounts.realm.key();
+        let bump = [ctx.bumps.governance];
+        let seeds: &[&[u8]] = &[b"governance", realm.as_ref(), &bump];
+        for instruction in &transaction.instructions {
+            invoke_signed(&instruction.to_instruction(), ctx.remaining_accounts, &[seeds])?;
+        }
+
+        transaction.executed = true;
+        proposal.executed_count += 1;
+        Ok(())
+    }
+
+    /// Closes voting once the deadline has passed, marking the proposal `Passed` or
+    /// `Failed`. Anyone may call this.
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.transition(ProposalAction::Finalize, Clock::get()?.unix_timestamp)?;
+        msg!("Proposal {} finalized: {:?}", proposal.key(), proposal.status);
+        Ok(())
+    }
+
+    /// Records the voter's choice, weighted by their voting power at the proposal's
+    /// snapshot. The vote record is a PDA of (proposal, voter), so a second vote from
+    /// the same wallet fails; use `change_vote` to switch sides.
+    pub fn cast_vote(ctx: Context<CastVote>, approve: bool) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let vote_record = &mut ctx.accounts.vote_record;
 
-        require!(proposal.status == ProposalStatus::Active, ErrorCode::ProposalNotActive);
+        let now = Clock::get()?.unix_timestamp;
+        proposal.require_open(now)?;
+        require!(proposal.vote_type == VoteType::Binary, ErrorCode::WrongVoteType);
+        ctx.accounts
+            .domain_config
+            .check_eligible(&ctx.accounts.voter.key(), ctx.accounts.ring_progress.as_deref())?;
+        let weight = vote_weight(&ctx.accounts.voter_history, proposal.snapshot_slot)?;
 
         vote_record.voter = ctx.accounts.voter.key();
         vote_record.proposal = proposal.key();
         vote_record.approve = approve;
+        vote_record.weight = weight;
+        vote_record.bump = ctx.bumps.vote_record;
 
-        if approve {
-            proposal.votes_for += 1; // Simplistic: 1 vote per user. Real gov uses token weight.
-        } else {
-            proposal.votes_against += 1;
+        proposal.add_vote(approve, weight)?;
+        Ok(())
+    }
+
+    pub fn change_vote(ctx: Context<ChangeVote>, approve: bool) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+
+        proposal.require_open(Clock::get()?.unix_timestamp)?;
+        require!(proposal.vote_type == VoteType::Binary, ErrorCode::WrongVoteType);
+        require!(vote_record.approve != approve, ErrorCode::VoteUnchanged);
+
+        proposal.remove_vote(vote_record.approve, vote_record.weight)?;
+        proposal.add_vote(approve, vote_record.weight)?;
+        vote_record.approve = approve;
+        Ok(())
+    }
+
+    /// Votes on a multiple-choice proposal. `choices` are option indices: exactly one
+    /// for plurality, or a ranking in order of preference for ranked choice.
+    pub fn cast_choice_vote(ctx: Context<CastVote>, choices: Vec<u8>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+
+        let now = Clock::get()?.unix_timestamp;
+        proposal.require_open(now)?;
+        proposal.validate_ballot(&choices)?;
+        ctx.accounts
+            .domain_config
+            .check_eligible(&ctx.accounts.voter.key(), ctx.accounts.ring_progress.as_deref())?;
+        let weight = vote_weight(&ctx.accounts.voter_history, proposal.snapshot_slot)?;
+
+        proposal.add_ballot(&choices, weight)?;
+        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.proposal = proposal.key();
+        vote_record.weight = weight;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.choices = choices;
+        Ok(())
+    }
+
+    pub fn change_choice_vote(ctx: Context<ChangeVote>, choices: Vec<u8>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+
+        proposal.require_open(Clock::get()?.unix_timestamp)?;
+        proposal.validate_ballot(&choices)?;
+        require!(vote_record.choices != choices, ErrorCode::VoteUnchanged);
+
+        proposal.remove_ballot(&vote_record.choices, vote_record.weight)?;
+        proposal.add_ballot(&choices, vote_record.weight)?;
+        vote_record.choices = choices;
+        Ok(())
+    }
+
+    /// Counts a batch of ballots for the current instant-runoff round, passed as
+    /// writable vote records in remaining accounts. Once every ballot is counted the
+    /// round closes: a majority option wins, otherwise the last-placed option is
+    /// eliminated and the next round starts.
+    pub fn tally_ranked_votes<'info>(ctx: Context<'_, '_, 'info, 'info, TallyRankedVotes<'info>>) -> Result<()> {
+        require!(ctx.remaining_accounts.len() <= MAX_TALLY_BATCH, ErrorCode::TallyBatchTooLarge);
+        let proposal = &mut ctx.accounts.proposal;
+        for info in ctx.remaining_accounts {
+            let mut vote_record = Account::<VoteRecord>::try_from(info)?;
+            require_keys_eq!(vote_record.proposal, proposal.key(), ErrorCode::InvalidVoteRecord);
+            proposal.count_ballot(&mut vote_record)?;
+            vote_record.exit(ctx.program_id)?;
         }
 
+        if proposal.counted == proposal.ballot_count {
+            proposal.transition(ProposalAction::Tally, Clock::get()?.unix_timestamp)?;
+            msg!("Proposal {} round {} closed: {:?}", proposal.key(), proposal.round, proposal.status);
+        }
         Ok(())
     }
 }
 
 #[derive(Accounts)]
-pub struct Initialize {}
+pub struct Initialize<'info> {
+    #[account(init, payer = admin, space = Realm::LEN, seeds = [b"realm", governing_mint.key().as_ref()], bump)]
+    pub realm: Account<'info, Realm>,
+    pub governing_mint: Account<'info, Mint>,
+    /// Holds proposal deposits until they are settled.
+    #[account(
+        init,
+        payer = admin,
+        token::mint = governing_mint,
+        token::authority = realm,
+        seeds = [b"deposit_vault", realm.key().as_ref()],
+        bump
+    )]
+    pub deposit_vault: Account<'info, TokenAccount>,
+    #[account(token::mint = governing_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+    /// Realms can only be created by the staking program's authority.
+    #[account(seeds = [b"config"], bump = staking_config.bump, seeds::program = grit_staking::ID)]
+    pub staking_config: Account<'info, StakingConfig>,
+    #[account(mut, address = staking_config.authority @ ErrorCode::NotStakingAuthority)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetGovConfig<'info> {
+    #[account(mut, seeds = [b"realm", realm.governing_mint.as_ref()], bump = realm.bump, has_one = admin)]
+    pub realm: Account<'info, Realm>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasury<'info> {
+    #[account(mut, seeds = [b"realm", realm.governing_mint.as_ref()], bump = realm.bump, has_one = admin)]
+    pub realm: Account<'info, Realm>,
+    #[account(token::mint = realm.governing_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+}
 
 #[derive(Accounts)]
+#[instruction(domain: Domain)]
+pub struct SetDomainConfig<'info> {
+    #[account(seeds = [b"realm", realm.governing_mint.as_ref()], bump = realm.bump, has_one = admin)]
+    pub realm: Account<'info, Realm>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = DomainConfig::LEN,
+        seeds = [b"domain", realm.key().as_ref(), &[domain as u8]],
+        bump
+    )]
+    pub domain_config: Account<'info, DomainConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(title: String, content_uri: String, content_hash: [u8; 32], domain: Domain)]
 pub struct CreateProposal<'info> {
-    #[account(init, payer = author, space = 8 + 32 + 64 + 256 + 8 + 8 + 2 + 8)]
+    #[account(mut, seeds = [b"realm", realm.governing_mint.as_ref()], bump = realm.bump)]
+    pub realm: Account<'info, Realm>,
+    #[account(
+        init,
+        payer = author,
+        space = Proposal::LEN,
+        seeds = [b"proposal", realm.key().as_ref(), realm.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
     pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"domain", realm.key().as_ref(), &[domain as u8]], bump = domain_config.bump)]
+    pub domain_config: Account<'info, DomainConfig>,
+    /// The author's Ascesis ring progress; required when the domain sets `min_ring`.
+    pub ring_progress: Option<Account<'info, RingProgress>>,
+    #[account(mut, seeds = [b"deposit_vault", realm.key().as_ref()], bump)]
+    pub deposit_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = realm.governing_mint, token::authority = author)]
+    pub author_token_account: Account<'info, TokenAccount>,
     #[account(mut)]
     pub author: Signer<'info>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(proposal_id: u64)]
-pub struct CastVote<'info> {
+pub struct OpenVoting<'info> {
+    pub realm: Account<'info, Realm>,
+    #[account(mut, has_one = realm, has_one = author)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"domain", realm.key().as_ref(), &[proposal.domain as u8]], bump = domain_config.bump)]
+    pub domain_config: Account<'info, DomainConfig>,
+    #[account(
+        seeds = [b"pool", realm.governing_mint.as_ref()],
+        bump = stake_pool.bump,
+        seeds::program = grit_staking::ID
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    /// Pool-wide voting power history, used for percentage quorums.
+    #[account(
+        seeds = [b"history", stake_pool.key().as_ref()],
+        bump = pool_history.bump,
+        seeds::program = grit_staking::ID
+    )]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    pub author: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(mut, has_one = author)]
+    pub proposal: Account<'info, Proposal>,
+    pub author: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDeposit<'info> {
+    #[account(seeds = [b"realm", realm.governing_mint.as_ref()], bump = realm.bump)]
+    pub realm: Account<'info, Realm>,
+    #[account(mut, has_one = realm)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut, seeds = [b"deposit_vault", realm.key().as_ref()], bump)]
+    pub deposit_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = realm.governing_mint, token::authority = proposal.author)]
+    pub author_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = realm.treasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct VetoProposal<'info> {
+    pub realm: Account<'info, Realm>,
+    #[account(mut, has_one = realm)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"domain", realm.key().as_ref(), &[proposal.domain as u8]], bump = domain_config.bump)]
+    pub domain_config: Account<'info, DomainConfig>,
+    pub vetoer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProposalMetadata<'info> {
+    #[account(mut, has_one = author)]
+    pub proposal: Account<'info, Proposal>,
+    pub author: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(instructions: Vec<ProposalInstruction>)]
+pub struct AddProposalTransaction<'info> {
+    #[account(mut, has_one = author)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        init,
+        payer = author,
+        space = ProposalTransaction::space(&instructions),
+        seeds = [b"proposal_tx", proposal.key().as_ref(), proposal.transaction_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    pub realm: Account<'info, Realm>,
+    #[account(mut, has_one = realm)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"proposal_tx", proposal.key().as_ref(), proposal_transaction.index.to_le_bytes().as_ref()],
+        bump = proposal_transaction.bump
+    )]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+    /// CHECK: Signs the proposal's instructions; holds no data. Each realm has its own.
+    #[account(seeds = [b"governance", realm.key().as_ref()], bump)]
+    pub governance: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
     #[account(mut)]
     pub proposal: Account<'info, Proposal>,
-    #[account(init, payer = voter, space = 8 + 32 + 32 + 1)]
+}
+
+#[derive(Accounts)]
+pub struct TallyRankedVotes<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    pub realm: Account<'info, Realm>,
+    #[account(mut, has_one = realm)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"domain", realm.key().as_ref(), &[proposal.domain as u8]], bump = domain_config.bump)]
+    pub domain_config: Account<'info, DomainConfig>,
+    /// The voter's Ascesis ring progress; required when the domain sets `min_ring`.
+    pub ring_progress: Option<Account<'info, RingProgress>>,
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::LEN,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
     pub vote_record: Account<'info, VoteRecord>,
+    /// The staking pool for the realm's governing mint.
+    #[account(
+        seeds = [b"pool", realm.governing_mint.as_ref()],
+        bump = stake_pool.bump,
+        seeds::program = grit_staking::ID
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    /// The voter's staking history, holding their own and delegated power over time.
+    #[account(
+        seeds = [b"history", stake_pool.key().as_ref(), voter.key().as_ref()],
+        bump = voter_history.bump,
+        seeds::program = grit_staking::ID
+    )]
+    pub voter_history: Box<Account<'info, StakeHistory>>,
     #[account(mut)]
     pub voter: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump = vote_record.bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    pub voter: Signer<'info>,
+}
+
+/// A proposal, addressed by its realm and sequential `id`.
 #[account]
+#[derive(Default)]
 pub struct Proposal {
+    pub realm: Pubkey,
+    pub id: u64,
+    pub bump: u8,
+    pub domain: Domain,
     pub author: Pubkey,
-    pub title: String, // Max 64 chars
-    pub description: String, // Max 256 chars
-    pub votes_for: u64,
-    pub votes_against: u64,
+    pub title: String,         // At most MAX_TITLE_LEN bytes
+    pub content_uri: String,   // At most MAX_URI_LEN bytes
+    pub content_hash: [u8; 32], // SHA-256 of the document at `content_uri`
+    pub votes_for: u128,     // Summed voting power
+    pub votes_against: u128,
     pub status: ProposalStatus,
     pub creation_time: i64,
+    pub deadline: i64,           // Votes are accepted until this time
+    pub quorum: u128,            // Votes needed for the result to count
+    pub pass_threshold_bps: u16, // Share of votes cast that `votes_for` must exceed
+    pub execute_after: i64,      // End of the timelock once the vote has passed
+    pub transaction_count: u16,
+    pub executed_count: u16,     // Transactions run so far, in index order
+    pub deposit: u64,            // Escrowed GRIT; zero once settled
+    pub refund_turnout: u128,    // Votes needed for the deposit to be refunded
+    pub vote_type: VoteType,
+    pub options: Vec<String>,    // Empty for binary proposals
+    pub option_votes: Vec<u128>, // Per-option tally for the current count round
+    pub ballot_weight: u128,     // Summed voting power of multiple-choice ballots
+    pub ballot_count: u32,
+    pub round: u8,               // Instant-runoff round; round 0 is counted as votes arrive
+    pub counted: u32,            // Ballots counted in the current round
+    pub eliminated: u8,          // Bitmask of options knocked out of the runoff
+    pub winner: Option<u8>,
+    pub snapshot_slot: u64,      // Vote weights are read as of this slot
+}
+
+impl Proposal {
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 1 + 32 + (4 + MAX_TITLE_LEN) + (4 + MAX_URI_LEN) + 32 + 16 + 16 + 1 + 8 + 8 + 16 + 2 + 8 + 2 + 2 + 8 + 16
+        + 1 + (4 + MAX_OPTIONS * (4 + MAX_OPTION_LEN)) + (4 + MAX_OPTIONS * 16) + 16 + 4 + 1 + 4 + 1 + 2 + 8;
+
+    /// The proposal state machine. Every status change goes through here:
+    ///
+    /// - Draft → Active (open voting) or Cancelled
+    /// - Active → Cancelled while no votes have been cast
+    /// - Active → Passed or Failed once the deadline has passed, or Tallying when a
+    ///   ranked-choice vote needs runoff rounds
+    /// - Tallying → Passed, Failed or another round once every ballot is counted
+    /// - Passed → Vetoed during the timelock
+    /// - Passed → Executed after the timelock, once its last transaction runs
+    pub fn transition(&mut self, action: ProposalAction, now: i64) -> Result<()> {
+        use ProposalAction as A;
+        use ProposalStatus as S;
+
+        let no_votes = self.turnout()? == 0;
+        let next = match (self.status, action) {
+            (S::Draft, A::OpenVoting) => S::Active,
+            (S::Draft, A::Cancel) => S::Cancelled,
+            (S::Active, A::Cancel) if no_votes => S::Cancelled,
+            (S::Active, A::Finalize) if now >= self.deadline => self.outcome()?,
+            (S::Tallying, A::Tally) if self.counted == self.ballot_count => self.close_round()?,
+            (S::Passed, A::Veto) if now < self.execute_after => S::Vetoed,
+            (S::Passed, A::Execute) if now >= self.execute_after && self.executed_count < self.transaction_count => {
+                if self.executed_count + 1 == self.transaction_count {
+                    S::Executed
+                } else {
+                    S::Passed
+                }
+            }
+            _ => return err!(ErrorCode::InvalidTransition),
+        };
+        self.status = next;
+        Ok(())
+    }
+
+    pub fn require_open(&self, now: i64) -> Result<()> {
+        require!(self.status == ProposalStatus::Active, ErrorCode::ProposalNotActive);
+        require!(now < self.deadline, ErrorCode::VotingClosed);
+        Ok(())
+    }
+
+    /// Result of the vote once turnout meets quorum. A binary proposal passes when
+    /// the share of votes for exceeds the pass threshold; a plurality vote passes
+    /// when one option leads outright; a ranked-choice vote is decided by `close_round`.
+    pub fn outcome(&mut self) -> Result<ProposalStatus> {
+        let total = self.turnout()?;
+        if total < self.quorum || total == 0 {
+            return Ok(ProposalStatus::Failed);
+        }
+        match self.vote_type {
+            VoteType::Binary => {
+                let for_share = self.votes_for.checked_mul(BPS_DENOMINATOR as u128).ok_or(ErrorCode::MathOverflow)?;
+                let threshold = total.checked_mul(self.pass_threshold_bps as u128).ok_or(ErrorCode::MathOverflow)?;
+                if for_share > threshold {
+                    Ok(ProposalStatus::Passed)
+                } else {
+                    Ok(ProposalStatus::Failed)
+                }
+            }
+            VoteType::Plurality => {
+                let top = self.option_votes.iter().copied().max().unwrap_or(0);
+                let mut leaders = (0..self.option_votes.len()).filter(|&i| self.option_votes[i] == top);
+                match (leaders.next(), leaders.next()) {
+                    (Some(winner), None) => {
+                        self.winner = Some(winner as u8);
+                        Ok(ProposalStatus::Passed)
+                    }
+                    _ => Ok(ProposalStatus::Failed), // Tied for first
+                }
+            }
+            VoteType::RankedChoice => self.close_round(),
+        }
+    }
+
+    /// Closes an instant-runoff round on the tallies in `option_votes`. An option with
+    /// a majority of the ballots still in play wins; otherwise the last-placed option
+    /// is eliminated and the ballots are re-counted. Ties favour the earlier option.
+    pub fn close_round(&mut self) -> Result<ProposalStatus> {
+        let continuing: Vec<usize> = (0..self.option_votes.len()).filter(|&i| self.eliminated & (1 << i) == 0).collect();
+        let mut in_play: u128 = 0;
+        for &i in &continuing {
+            in_play = in_play.checked_add(self.option_votes[i]).ok_or(ErrorCode::MathOverflow)?;
+        }
+        if in_play == 0 {
+            return Ok(ProposalStatus::Failed); // Every ballot is exhausted
+        }
+
+        let leader = continuing.iter().copied().rev().max_by_key(|&i| self.option_votes[i]).unwrap_or(0);
+        if self.option_votes[leader].checked_mul(2).ok_or(ErrorCode::MathOverflow)? > in_play {
+            self.winner = Some(leader as u8);
+            return Ok(ProposalStatus::Passed);
+        }
+
+        let last = continuing.iter().copied().rev().min_by_key(|&i| self.option_votes[i]).unwrap_or(0);
+        self.eliminated |= 1 << last;
+        self.round = self.round.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.counted = 0;
+        self.option_votes.iter_mut().for_each(|votes| *votes = 0);
+        Ok(ProposalStatus::Tallying)
+    }
+
+    /// Total voting power cast, whatever the vote type.
+    pub fn turnout(&self) -> Result<u128> {
+        let total = self.votes_for.checked_add(self.votes_against).ok_or(ErrorCode::MathOverflow)?;
+        Ok(total.checked_add(self.ballot_weight).ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// Checks a multiple-choice ballot: distinct, valid option indices, and exactly
+    /// one of them for plurality.
+    pub fn validate_ballot(&self, choices: &[u8]) -> Result<()> {
+        let max_len = match self.vote_type {
+            VoteType::Binary => return err!(ErrorCode::WrongVoteType),
+            VoteType::Plurality => 1,
+            VoteType::RankedChoice => self.options.len(),
+        };
+        require!(!choices.is_empty() && choices.len() <= max_len, ErrorCode::InvalidBallot);
+        let mut seen: u8 = 0;
+        for &choice in choices {
+            require!((choice as usize) < self.options.len(), ErrorCode::InvalidBallot);
+            require!(seen & (1 << choice) == 0, ErrorCode::InvalidBallot);
+            seen |= 1 << choice;
+        }
+        Ok(())
+    }
+
+    /// Adds a ballot to the round-0 tally, which counts first choices.
+    pub fn add_ballot(&mut self, choices: &[u8], weight: u128) -> Result<()> {
+        let tally = &mut self.option_votes[choices[0] as usize];
+        *tally = tally.checked_add(weight).ok_or(ErrorCode::MathOverflow)?;
+        self.ballot_weight = self.ballot_weight.checked_add(weight).ok_or(ErrorCode::MathOverflow)?;
+        self.ballot_count = self.ballot_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn remove_ballot(&mut self, choices: &[u8], weight: u128) -> Result<()> {
+        let tally = &mut self.option_votes[choices[0] as usize];
+        *tally = tally.checked_sub(weight).ok_or(ErrorCode::MathOverflow)?;
+        self.ballot_weight = self.ballot_weight.checked_sub(weight).ok_or(ErrorCode::MathOverflow)?;
+        self.ballot_count = self.ballot_count.checked_sub(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Counts one ballot toward the current runoff round: its weight goes to its most
+    /// preferred option still in play. Ballots whose options are all eliminated are exhausted.
+    pub fn count_ballot(&mut self, vote_record: &mut VoteRecord) -> Result<()> {
+        require!(self.status == ProposalStatus::Tallying, ErrorCode::ProposalNotTallying);
+        require!(vote_record.counted_round < self.round, ErrorCode::BallotAlreadyCounted);
+        if let Some(&choice) = vote_record.choices.iter().find(|&&c| self.eliminated & (1 << c) == 0) {
+            let tally = &mut self.option_votes[choice as usize];
+            *tally = tally.checked_add(vote_record.weight).ok_or(ErrorCode::MathOverflow)?;
+        }
+        vote_record.counted_round = self.round;
+        self.counted = self.counted.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether a finished proposal's deposit goes back to the author. Vetoed proposals,
+    /// drafts withdrawn before voting opened and those that drew less than
+    /// `refund_turnout`, including ones cancelled after opening, are not refunded, so a
+    /// draft's proposal id always costs its deposit. A passed proposal settles only
+    /// after its timelock, since it can still be vetoed.
+    pub fn deposit_refundable(&self, now: i64) -> Result<bool> {
+        require!(self.deposit > 0, ErrorCode::DepositSettled);
+        let turnout = self.turnout()?;
+        match self.status {
+            ProposalStatus::Cancelled if self.deadline == 0 => Ok(false), // `refund_turnout` is unset before voting
+            ProposalStatus::Vetoed => Ok(false),
+            ProposalStatus::Passed if now < self.execute_after => err!(ErrorCode::DepositLocked),
+            ProposalStatus::Passed | ProposalStatus::Failed | ProposalStatus::Executed | ProposalStatus::Cancelled => {
+                Ok(turnout >= self.refund_turnout)
+            }
+            ProposalStatus::Draft | ProposalStatus::Active | ProposalStatus::Tallying => err!(ErrorCode::DepositLocked),
+        }
+    }
+
+    pub fn add_vote(&mut self, approve: bool, weight: u128) -> Result<()> {
+        let tally = if approve { &mut self.votes_for } else { &mut self.votes_against };
+        *tally = tally.checked_add(weight).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn remove_vote(&mut self, approve: bool, weight: u128) -> Result<()> {
+        let tally = if approve { &mut self.votes_for } else { &mut self.votes_against };
+        *tally = tally.checked_sub(weight).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
 }
 
 #[account]
@@ -82,18 +863,699 @@ pub struct VoteRecord {
     pub voter: Pubkey,
     pub proposal: Pubkey,
     pub approve: bool,
+    pub weight: u128, // Voting power counted for this vote
+    pub bump: u8,
+    pub choices: Vec<u8>,  // Option indices by preference; empty for binary votes
+    pub counted_round: u8, // Last runoff round this ballot was counted in
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 16 + 1 + (4 + MAX_OPTIONS) + 1;
+}
+
+/// Instructions a proposal runs when executed, in order.
+#[account]
+#[derive(Default)]
+pub struct ProposalTransaction {
+    pub proposal: Pubkey,
+    pub index: u16,
+    pub executed: bool,
+    pub bump: u8,
+    pub instructions: Vec<ProposalInstruction>,
+}
+
+impl ProposalTransaction {
+    pub fn space(instructions: &[ProposalInstruction]) -> usize {
+        8 + 32 + 2 + 1 + 1 + 4 + instructions.iter().map(ProposalInstruction::serialized_len).sum::<usize>()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl ProposalInstruction {
+    pub fn serialized_len(&self) -> usize {
+        32 + 4 + self.accounts.len() * ProposalAccountMeta::LEN + 4 + self.data.len()
+    }
+
+    pub fn to_instruction(&self) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta { pubkey: meta.pubkey, is_signer: meta.is_signer, is_writable: meta.is_writable })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccountMeta {
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+/// A governance space for one governing mint. Proposals are numbered from
+/// `proposal_count`, so clients can page through them by id.
+#[account]
+#[derive(Default)]
+pub struct Realm {
+    pub admin: Pubkey,
+    pub governing_mint: Pubkey,
+    pub config: GovConfig,
+    pub proposal_count: u64,
+    pub bump: u8,
+    pub guardian: Pubkey, // May veto passed proposals during their timelock
+    pub treasury: Pubkey, // Token account receiving forfeited deposits
+}
+
+impl Realm {
+    pub const LEN: usize = 8 + 32 + 32 + GovConfig::LEN + 8 + 1 + 32 + 32;
+}
+
+#[event]
+pub struct DepositSettled {
+    pub proposal: Pubkey,
+    pub author: Pubkey,
+    pub amount: u64,
+    pub refunded: bool,
+}
+
+#[event]
+pub struct ProposalMetadataUpdated {
+    pub proposal: Pubkey,
+    pub title: String,
+    pub content_uri: String,
+    pub content_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Realm of a proposal, mirroring `Domain` in `lib/domain-gates.ts`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Domain {
+    #[default]
+    Sovereign,
+    Ascesis,
+    Heritage,
+    Market,
+}
+
+/// Per-domain rules: who may propose and vote, and an optional quorum override.
+#[account]
+#[derive(Default)]
+pub struct DomainConfig {
+    pub realm: Pubkey,
+    pub domain: Domain,
+    pub quorum: Option<Quorum>, // None uses the realm's quorum
+    pub min_ring: u8,           // Ascesis ring a participant must have reached; 0 for none
+    pub council: Vec<Pubkey>,   // When non-empty, only these wallets may participate
+    pub bump: u8,
+}
+
+impl DomainConfig {
+    pub const LEN: usize = 8 + 32 + 1 + (1 + Quorum::LEN) + 1 + (4 + 32 * MAX_COUNCIL) + 1;
+
+    pub fn check_eligible(&self, wallet: &Pubkey, ring_progress: Option<&RingProgress>) -> Result<()> {
+        if !self.council.is_empty() {
+            require!(self.council.contains(wallet), ErrorCode::NotCouncilMember);
+        }
+        if self.min_ring > 0 {
+            let progress = ring_progress.ok_or(ErrorCode::RingTooLow)?;
+            require_keys_eq!(progress.owner, *wallet, ErrorCode::RingTooLow);
+            require!(progress.ring_level >= self.min_ring, ErrorCode::RingTooLow);
+        }
+        Ok(())
+    }
+}
+
+/// Voting rules applied to new proposals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct GovConfig {
+    pub voting_period: i64,
+    pub quorum: Quorum,
+    pub pass_threshold_bps: u16,
+    pub timelock: i64, // Delay between a proposal's deadline and its execution
+    pub proposal_deposit: u64, // GRIT escrowed by each new proposal
+    pub refund_turnout: Quorum, // Turnout a proposal needs to get its deposit back
+}
+
+impl GovConfig {
+    pub const LEN: usize = 8 + Quorum::LEN + 2 + 8 + 8 + Quorum::LEN;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.voting_period > 0 && self.voting_period <= MAX_VOTING_PERIOD,
+            ErrorCode::InvalidVotingPeriod
+        );
+        require!(self.timelock >= 0, ErrorCode::InvalidTimelock);
+        require!((self.pass_threshold_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidBps);
+        for quorum in [self.quorum, self.refund_turnout] {
+            if let Quorum::SupplyBps(bps) = quorum {
+                require!(bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Minimum turnout for a proposal, in voting power.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quorum {
+    Absolute(u128),
+    SupplyBps(u16), // Share of all staked voting power at the proposal's snapshot
+}
+
+impl Default for Quorum {
+    fn default() -> Self {
+        Quorum::Absolute(0)
+    }
+}
+
+impl Quorum {
+    pub const LEN: usize = 1 + 16;
+
+    pub fn required_votes(&self, staked_power: u64) -> Result<u128> {
+        match *self {
+            Quorum::Absolute(votes) => Ok(votes),
+            Quorum::SupplyBps(bps) => Ok((staked_power as u128)
+                .checked_mul(bps as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                / BPS_DENOMINATOR as u128),
+        }
+    }
+}
+
+pub fn validate_options(vote_type: VoteType, options: &[String]) -> Result<()> {
+    if vote_type == VoteType::Binary {
+        require!(options.is_empty(), ErrorCode::InvalidOptions);
+        return Ok(());
+    }
+    require!((2..=MAX_OPTIONS).contains(&options.len()), ErrorCode::InvalidOptions);
+    for option in options {
+        require!(!option.is_empty() && option.len() <= MAX_OPTION_LEN, ErrorCode::InvalidOptions);
+    }
+    Ok(())
+}
+
+pub fn validate_metadata(title: &str, content_uri: &str) -> Result<()> {
+    require!(!title.is_empty(), ErrorCode::EmptyTitle);
+    require!(title.len() <= MAX_TITLE_LEN, ErrorCode::TitleTooLong);
+    require!(!content_uri.is_empty(), ErrorCode::EmptyContentUri);
+    require!(content_uri.len() <= MAX_URI_LEN, ErrorCode::ContentUriTooLong);
+    Ok(())
+}
+
+/// Voting power at a proposal's snapshot: the voter's own resonance (staked amount ×
+/// (Heritage multiplier)², as in `calculateVotingPower`) plus power delegated to them.
+/// Delegators hold none of their own, so their power is counted only by their delegatee.
+pub fn vote_weight(voter_history: &StakeHistory, snapshot_slot: u64) -> Result<u128> {
+    let power = voter_history.weight_at(snapshot_slot);
+    require!(power > 0, ErrorCode::NoVotingPower);
+    Ok(power as u128)
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
 pub enum ProposalStatus {
+    #[default]
     Active,
     Passed,
     Failed,
     Executed,
+    Draft,
+    Cancelled,
+    Vetoed,
+    Tallying, // Ranked-choice runoff in progress; see `tally_ranked_votes`
+}
+
+/// How a proposal is voted on.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum VoteType {
+    #[default]
+    Binary, // For or against
+    Plurality,    // One option per ballot; the most-voted option wins
+    RankedChoice, // Ranked ballots, decided by instant runoff
+}
+
+/// Events that move a proposal between `ProposalStatus`es; see `Proposal::transition`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalAction {
+    OpenVoting,
+    Cancel,
+    Finalize,
+    Veto,
+    Execute, // Runs one transaction; the last one completes execution
+    Tally,   // Closes a runoff round once all ballots are counted
 }
 
 #[error_code]
 pub enum ErrorCode {
     #[msg("Proposal is not active.")]
     ProposalNotActive,
+    #[msg("Vote already records this choice.")]
+    VoteUnchanged,
+    #[msg("Arithmetic overflow.")]
+    MathOverflow,
+    #[msg("Voter had no voting power at the proposal's snapshot.")]
+    NoVotingPower,
+    #[msg("The voting period has ended.")]
+    VotingClosed,
+    #[msg("Voting period must be positive and at most the staking history's retention.")]
+    InvalidVotingPeriod,
+    #[msg("Basis points out of range.")]
+    InvalidBps,
+    #[msg("Timelock cannot be negative.")]
+    InvalidTimelock,
+    #[msg("A proposal transaction needs at least one instruction.")]
+    EmptyTransaction,
+    #[msg("Proposal transactions must be executed in order.")]
+    TransactionOutOfOrder,
+    #[msg("Council exceeds the maximum size.")]
+    CouncilTooLarge,
+    #[msg("Only council members may participate in this domain.")]
+    NotCouncilMember,
+    #[msg("Ascesis ring is below this domain's minimum.")]
+    RingTooLow,
+    #[msg("Proposal title is empty.")]
+    EmptyTitle,
+    #[msg("Proposal title exceeds 64 bytes.")]
+    TitleTooLong,
+    #[msg("Proposal content URI is empty.")]
+    EmptyContentUri,
+    #[msg("Proposal content URI exceeds 200 bytes.")]
+    ContentUriTooLong,
+    #[msg("Only draft proposals can be edited.")]
+    ProposalNotDraft,
+    #[msg("This action is not allowed in the proposal's current state.")]
+    InvalidTransition,
+    #[msg("Only the guardian or a domain council member may veto.")]
+    Unauthorized,
+    #[msg("Only the staking authority may create a realm.")]
+    NotStakingAuthority,
+    #[msg("The proposal's deposit cannot be settled yet.")]
+    DepositLocked,
+    #[msg("The proposal's deposit has already been settled.")]
+    DepositSettled,
+    #[msg("This action does not apply to the proposal's vote type.")]
+    WrongVoteType,
+    #[msg("Multiple-choice proposals need 2 to 8 options of at most 32 bytes each.")]
+    InvalidOptions,
+    #[msg("Ballot choices must be distinct options; plurality ballots pick exactly one.")]
+    InvalidBallot,
+    #[msg("Proposal is not being tallied.")]
+    ProposalNotTallying,
+    #[msg("Ballot has already been counted in this round.")]
+    BallotAlreadyCounted,
+    #[msg("Too many ballots in one tally batch.")]
+    TallyBatchTooLarge,
+    #[msg("Vote record belongs to another proposal.")]
+    InvalidVoteRecord,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_vote_moves_tally() {
+        let mut proposal = Proposal::default();
+        proposal.add_vote(true, 2_250).unwrap();
+        proposal.add_vote(false, 1_000).unwrap();
+
+        proposal.remove_vote(true, 2_250).unwrap();
+        proposal.add_vote(false, 2_250).unwrap();
+        assert_eq!((proposal.votes_for, proposal.votes_against), (0, 3_250));
+
+        assert!(proposal.remove_vote(true, 1).is_err());
+    }
+
+    #[test]
+    fn test_finalize_outcome() {
+        let mut proposal = Proposal { quorum: 1_000, pass_threshold_bps: 5_000, ..Default::default() };
+        proposal.add_vote(true, 600).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Failed); // Below quorum
+
+        proposal.add_vote(false, 600).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Failed); // A tie does not pass
+
+        proposal.add_vote(true, 1).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Passed);
+
+        proposal.pass_threshold_bps = 6_667; // Supermajority
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Failed);
+    }
+
+    #[test]
+    fn test_voting_deadline_and_quorum() {
+        let proposal = Proposal { deadline: 100, ..Default::default() };
+        assert!(proposal.require_open(99).is_ok());
+        assert!(proposal.require_open(100).is_err());
+
+        assert_eq!(Quorum::Absolute(5_000).required_votes(1_000_000).unwrap(), 5_000);
+        assert_eq!(Quorum::SupplyBps(400).required_votes(1_000_000).unwrap(), 40_000);
+
+        let config = GovConfig { voting_period: 86_400, pass_threshold_bps: 5_000, ..Default::default() };
+        assert!(config.validate().is_ok());
+        assert!(GovConfig { voting_period: 0, ..config }.validate().is_err());
+        assert!(GovConfig { voting_period: MAX_VOTING_PERIOD + 1, ..config }.validate().is_err());
+        assert!(GovConfig { quorum: Quorum::SupplyBps(10_001), ..config }.validate().is_err());
+        assert!(GovConfig { refund_turnout: Quorum::SupplyBps(10_001), ..config }.validate().is_err());
+        assert!(GovConfig { pass_threshold_bps: 10_000, ..config }.validate().is_err());
+        assert!(GovConfig { timelock: -1, ..config }.validate().is_err());
+    }
+
+    #[test]
+    fn test_deposit_settlement() {
+        let mut proposal = Proposal { deposit: 1_000, refund_turnout: 500, execute_after: 200, ..Default::default() };
+        for status in [ProposalStatus::Draft, ProposalStatus::Active] {
+            proposal.status = status;
+            assert!(proposal.deposit_refundable(300).is_err());
+        }
+        // Withdrawing a draft still costs the deposit, so drafts cannot spam proposal ids.
+        proposal.status = ProposalStatus::Cancelled;
+        assert!(!proposal.deposit_refundable(0).unwrap());
+        // Cancelling after voting opens cannot dodge the turnout requirement.
+        let opened = Proposal { deadline: 100, ..proposal.clone() };
+        assert!(!opened.deposit_refundable(300).unwrap());
+        proposal.status = ProposalStatus::Vetoed;
+        assert!(!proposal.deposit_refundable(0).unwrap());
+
+        proposal.status = ProposalStatus::Failed;
+        proposal.add_vote(false, 499).unwrap();
+        assert!(!proposal.deposit_refundable(300).unwrap()); // Negligible turnout
+        proposal.add_vote(true, 1).unwrap();
+        assert!(proposal.deposit_refundable(300).unwrap());
+
+        proposal.status = ProposalStatus::Passed;
+        assert!(proposal.deposit_refundable(199).is_err()); // Still vetoable
+        assert!(proposal.deposit_refundable(200).unwrap());
+
+        proposal.deposit = 0;
+        assert!(proposal.deposit_refundable(300).is_err());
+    }
+
+    #[test]
+    fn test_proposal_transaction_layout() {
+        let realm = Pubkey::new_unique();
+        let governance = Pubkey::find_program_address(&[b"governance", realm.as_ref()], &crate::ID).0;
+        let instruction = ProposalInstruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                ProposalAccountMeta { pubkey: governance, is_signer: true, is_writable: false },
+                ProposalAccountMeta { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: true },
+            ],
+            data: vec![1, 2, 3, 4, 5],
+        };
+        let transaction = ProposalTransaction { instructions: vec![instruction.clone(); 2], ..Default::default() };
+        assert_eq!(
+            ProposalTransaction::space(&transaction.instructions),
+            8 + transaction.try_to_vec().unwrap().len()
+        );
+
+        let ix = instruction.to_instruction();
+        assert_eq!(ix.program_id, instruction.program_id);
+        assert!(ix.accounts[0].is_signer && !ix.accounts[0].is_writable);
+        assert_eq!(ix.data, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_proposal_addresses_follow_counter() {
+        let realm = Pubkey::find_program_address(&[b"realm", Pubkey::new_unique().as_ref()], &crate::ID).0;
+        let address = |id: u64| Pubkey::find_program_address(&[b"proposal", realm.as_ref(), &id.to_le_bytes()], &crate::ID).0;
+        assert_ne!(address(0), address(1));
+        assert_eq!(address(7), address(7));
+
+        let account = Realm { proposal_count: 3, ..Default::default() };
+        assert_eq!(account.try_to_vec().unwrap().len() + 8, Realm::LEN);
+    }
+
+    #[test]
+    fn test_domain_eligibility() {
+        let member = Pubkey::new_unique();
+        let outsider = Pubkey::new_unique();
+        let open = DomainConfig::default();
+        assert!(open.check_eligible(&outsider, None).is_ok());
+
+        let council = DomainConfig { council: vec![member], ..Default::default() };
+        assert!(council.check_eligible(&member, None).is_ok());
+        assert!(council.check_eligible(&outsider, None).is_err());
+
+        let ascesis = DomainConfig { domain: Domain::Ascesis, min_ring: 2, ..Default::default() };
+        let progress = |owner, ring_level| RingProgress { owner, ring_level, last_advance_time: 0, bump: 0 };
+        assert!(ascesis.check_eligible(&member, None).is_err());
+        assert!(ascesis.check_eligible(&member, Some(&progress(member, 1))).is_err());
+        assert!(ascesis.check_eligible(&member, Some(&progress(member, 3))).is_ok());
+        // Someone else's progress does not count.
+        assert!(ascesis.check_eligible(&outsider, Some(&progress(member, 3))).is_err());
+
+        let full = DomainConfig { council: vec![member; MAX_COUNCIL], quorum: Some(Quorum::Absolute(1)), ..Default::default() };
+        assert_eq!(full.try_to_vec().unwrap().len() + 8, DomainConfig::LEN);
+    }
+
+    #[test]
+    fn test_proposal_metadata_bounds() {
+        let uri = "https://example.r2.cloudflarestorage.com/proposals/1.md";
+        assert!(validate_metadata("Fund the Lung", uri).is_ok());
+        assert!(validate_metadata("", uri).is_err());
+        assert!(validate_metadata(&"x".repeat(MAX_TITLE_LEN), uri).is_ok());
+        assert!(validate_metadata(&"x".repeat(MAX_TITLE_LEN + 1), uri).is_err());
+        // Limits are in bytes, so multi-byte characters count fully.
+        assert!(validate_metadata(&"é".repeat(33), uri).is_err());
+        assert!(validate_metadata("Fund the Lung", "").is_err());
+        assert!(validate_metadata("Fund the Lung", &"u".repeat(MAX_URI_LEN + 1)).is_err());
+
+        let proposal = Proposal {
+            title: "x".repeat(MAX_TITLE_LEN),
+            content_uri: "u".repeat(MAX_URI_LEN),
+            options: vec!["o".repeat(MAX_OPTION_LEN); MAX_OPTIONS],
+            option_votes: vec![0; MAX_OPTIONS],
+            winner: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(proposal.try_to_vec().unwrap().len() + 8, Proposal::LEN);
+    }
+
+    const STATUSES: [ProposalStatus; 8] = [
+        ProposalStatus::Draft,
+        ProposalStatus::Active,
+        ProposalStatus::Passed,
+        ProposalStatus::Failed,
+        ProposalStatus::Executed,
+        ProposalStatus::Cancelled,
+        ProposalStatus::Vetoed,
+        ProposalStatus::Tallying,
+    ];
+    const ACTIONS: [ProposalAction; 6] = [
+        ProposalAction::OpenVoting,
+        ProposalAction::Cancel,
+        ProposalAction::Finalize,
+        ProposalAction::Veto,
+        ProposalAction::Execute,
+        ProposalAction::Tally,
+    ];
+
+    /// A proposal in `status` with every time and vote guard satisfied for `action`.
+    fn guarded(status: ProposalStatus, action: ProposalAction) -> (Proposal, i64) {
+        let proposal = Proposal {
+            status,
+            deadline: 100,
+            execute_after: 200,
+            quorum: 1,
+            pass_threshold_bps: 5_000,
+            votes_for: if action == ProposalAction::Finalize { 10 } else { 0 },
+            transaction_count: 1,
+            vote_type: if action == ProposalAction::Tally { VoteType::RankedChoice } else { VoteType::Binary },
+            option_votes: if action == ProposalAction::Tally { vec![10, 0] } else { Vec::new() },
+            ..Default::default()
+        };
+        let now = match action {
+            ProposalAction::Finalize => 100,
+            ProposalAction::Veto => 150,
+            ProposalAction::Execute => 200,
+            _ => 50,
+        };
+        (proposal, now)
+    }
+
+    #[test]
+    fn test_transition_table() {
+        use ProposalAction as A;
+        use ProposalStatus as S;
+        let allowed = |status, action| match (status, action) {
+            (S::Draft, A::OpenVoting) => Some(S::Active),
+            (S::Draft, A::Cancel) => Some(S::Cancelled),
+            (S::Active, A::Cancel) => Some(S::Cancelled),
+            (S::Active, A::Finalize) => Some(S::Passed),
+            (S::Passed, A::Veto) => Some(S::Vetoed),
+            (S::Passed, A::Execute) => Some(S::Executed),
+            (S::Tallying, A::Tally) => Some(S::Passed),
+            _ => None,
+        };
+
+        for status in STATUSES {
+            for action in ACTIONS {
+                let (mut proposal, now) = guarded(status, action);
+                let result = proposal.transition(action, now);
+                match allowed(status, action) {
+                    Some(next) => {
+                        assert!(result.is_ok(), "{:?} -{:?}-> should be allowed", status, action);
+                        assert_eq!(proposal.status, next, "{:?} -{:?}->", status, action);
+                    }
+                    None => {
+                        assert!(result.is_err(), "{:?} -{:?}-> should be rejected", status, action);
+                        assert_eq!(proposal.status, status);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transition_guards() {
+        // Cancelling is only possible before any votes arrive.
+        let (mut proposal, now) = guarded(ProposalStatus::Active, ProposalAction::Cancel);
+        proposal.add_vote(false, 1).unwrap();
+        assert!(proposal.transition(ProposalAction::Cancel, now).is_err());
+
+        // Finalizing waits for the deadline and can fail the vote.
+        let (mut proposal, _) = guarded(ProposalStatus::Active, ProposalAction::Finalize);
+        assert!(proposal.transition(ProposalAction::Finalize, 99).is_err());
+        proposal.votes_against = 10;
+        proposal.transition(ProposalAction::Finalize, 100).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Failed);
+
+        // Vetoes are only possible during the timelock; execution only after it.
+        let (mut proposal, _) = guarded(ProposalStatus::Passed, ProposalAction::Veto);
+        assert!(proposal.transition(ProposalAction::Veto, 200).is_err());
+        assert!(proposal.transition(ProposalAction::Execute, 199).is_err());
+
+        // Execution stays `Passed` until the last transaction runs.
+        proposal.transaction_count = 2;
+        proposal.transition(ProposalAction::Execute, 200).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Passed);
+        proposal.executed_count = 1;
+        proposal.transition(ProposalAction::Execute, 200).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
+
+        // A passed proposal without transactions has nothing to execute.
+        let (mut proposal, now) = guarded(ProposalStatus::Passed, ProposalAction::Execute);
+        proposal.transaction_count = 0;
+        assert!(proposal.transition(ProposalAction::Execute, now).is_err());
+    }
+
+    fn ballot(choices: &[u8], weight: u128) -> VoteRecord {
+        VoteRecord {
+            voter: Pubkey::new_unique(),
+            proposal: Pubkey::default(),
+            approve: false,
+            weight,
+            bump: 0,
+            choices: choices.to_vec(),
+            counted_round: 0,
+        }
+    }
+
+    fn choice_proposal(vote_type: VoteType, options: usize) -> Proposal {
+        let options: Vec<String> = (0..options).map(|i| format!("Track {}", i)).collect();
+        validate_options(vote_type, &options).unwrap();
+        Proposal { vote_type, option_votes: vec![0; options.len()], options, status: ProposalStatus::Active, ..Default::default() }
+    }
+
+    #[test]
+    fn test_ballot_validation() {
+        let options = vec!["A".to_string(); MAX_OPTIONS + 1];
+        assert!(validate_options(VoteType::Binary, &options[..2]).is_err());
+        assert!(validate_options(VoteType::Plurality, &options[..1]).is_err());
+        assert!(validate_options(VoteType::RankedChoice, &options).is_err());
+        assert!(validate_options(VoteType::Plurality, &["A".to_string(), "x".repeat(MAX_OPTION_LEN + 1)]).is_err());
+
+        let plurality = choice_proposal(VoteType::Plurality, 3);
+        assert!(plurality.validate_ballot(&[2]).is_ok());
+        assert!(plurality.validate_ballot(&[0, 1]).is_err());
+        assert!(plurality.validate_ballot(&[3]).is_err());
+
+        let ranked = choice_proposal(VoteType::RankedChoice, 3);
+        assert!(ranked.validate_ballot(&[2, 0]).is_ok());
+        assert!(ranked.validate_ballot(&[]).is_err());
+        assert!(ranked.validate_ballot(&[1, 1]).is_err());
+        assert!(Proposal::default().validate_ballot(&[0]).is_err());
+    }
+
+    #[test]
+    fn test_plurality_outcome() {
+        let mut proposal = choice_proposal(VoteType::Plurality, 3);
+        proposal.add_ballot(&[1], 500).unwrap();
+        proposal.add_ballot(&[2], 500).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Failed); // Tied for first
+
+        proposal.add_ballot(&[0], 200).unwrap();
+        proposal.remove_ballot(&[2], 500).unwrap();
+        proposal.add_ballot(&[0], 500).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Passed);
+        assert_eq!(proposal.winner, Some(0));
+
+        proposal.quorum = 2_000;
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Failed);
+    }
+
+    #[test]
+    fn test_ranked_choice_runoff() {
+        // First choices: A 40, B 35, C 25. C is eliminated and its voters prefer B.
+        let mut proposal = choice_proposal(VoteType::RankedChoice, 3);
+        let mut ballots = vec![ballot(&[0], 40), ballot(&[1, 0], 35), ballot(&[2, 1], 20), ballot(&[2], 5)];
+        for b in &ballots {
+            proposal.add_ballot(&b.choices, b.weight).unwrap();
+        }
+        proposal.deadline = 100;
+        proposal.transition(ProposalAction::Finalize, 100).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Tallying);
+        assert_eq!((proposal.round, proposal.eliminated), (1, 0b100));
+
+        // The round only closes once every ballot is counted, and each counts once.
+        proposal.count_ballot(&mut ballots[0]).unwrap();
+        assert!(proposal.count_ballot(&mut ballots[0]).is_err());
+        assert!(proposal.transition(ProposalAction::Tally, 100).is_err());
+        for b in &mut ballots[1..] {
+            proposal.count_ballot(b).unwrap();
+        }
+        assert_eq!(proposal.option_votes, vec![40, 55, 0]); // The last C ballot is exhausted
+        proposal.transition(ProposalAction::Tally, 100).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Passed);
+        assert_eq!(proposal.winner, Some(1));
+        assert_eq!(proposal.turnout().unwrap(), 100);
+    }
+
+    #[test]
+    fn test_ranked_choice_majority_in_first_round() {
+        let mut proposal = choice_proposal(VoteType::RankedChoice, 4);
+        proposal.add_ballot(&[3, 0], 60).unwrap();
+        proposal.add_ballot(&[0, 3], 40).unwrap();
+        assert_eq!(proposal.outcome().unwrap(), ProposalStatus::Passed);
+        assert_eq!((proposal.winner, proposal.round), (Some(3), 0));
+    }
+
+    #[test]
+    fn test_vote_weight_reads_snapshot() {
+        let mut history = StakeHistory::default();
+        history.record(10, 1_000).unwrap();
+        history.record(20, 2_250).unwrap(); // Heritage age refreshed by `refresh_voting_power`, or power delegated in
+        assert_eq!(vote_weight(&history, 15).unwrap(), 1_000);
+        assert_eq!(vote_weight(&history, 20).unwrap(), 2_250);
+
+        // Stake that arrives after the snapshot does not count, and neither does power
+        // delegated away before it.
+        assert!(vote_weight(&history, 9).is_err());
+        history.record(30, 0).unwrap();
+        assert!(vote_weight(&history, 30).is_err());
+    }
 }
@@ -1,31 +1,221 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::metadata::{Metadata, MetadataAccount};
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::{metadata_pointer, ExtensionType};
+use anchor_spl::token_2022::spl_token_2022::{self, instruction::AuthorityType};
+use anchor_spl::token_2022::{self as token_2022, Token2022};
+use spl_token_metadata_interface::state::TokenMetadata;
 
 declare_id!("G9Xq99jdwuvQD1nGGhW1C3TYuc6iRz78faoscQqmX2D7");
 
+/// Number of recent scars kept on an `AscesisRecord` before the oldest is overwritten.
+pub const MAX_SCARS: usize = 16;
+
+/// WZRD track rarities, in `RarityTier` order.
+pub const RARITY_TIERS: usize = 4;
+
+/// Collection NFTs that can boost a single position.
+pub const MAX_BOOSTERS: usize = 3;
+
+/// Heritage multiplier: +10% per 30 days staked, up to 2.5x.
+pub const HERITAGE_MONTH: i64 = 30 * 86_400;
+pub const HERITAGE_BONUS_BPS_PER_MONTH: u64 = 1_000;
+pub const MAX_HERITAGE_MULTIPLIER_BPS: u64 = 25_000;
+
+/// Stake ages, in days, that earn a Heritage badge; `milestone` indexes this table.
+pub const HERITAGE_MILESTONE_DAYS: [i64; 4] = [14, 30, 90, 365];
+pub const HERITAGE_BADGE_SYMBOL: &str = "HRTG";
+
+/// Current `UserStake` layout version; accounts written before versioning are v0.
+pub const USER_STAKE_VERSION: u8 = 1;
+
+/// Checkpoints a `StakeHistory` can hold.
+pub const MAX_CHECKPOINTS: usize = 32;
+
+/// Longest voting period, in seconds, a governance realm may configure.
+pub const MAX_VOTING_PERIOD: i64 = 7 * 86_400;
+
+/// Slots for which a wallet's `StakeHistory` stays exact, covering every snapshot a
+/// live vote can read. Slots run at about 2.5 per second, so 3 leaves a margin.
+pub const HISTORY_RETENTION_SLOTS: u64 = MAX_VOTING_PERIOD as u64 * 3;
+
+/// Slots, about a day, between `refresh_voting_power` cranks on one wallet's history.
+pub const REFRESH_INTERVAL_SLOTS: u64 = 216_000;
+
+/// Shortest `compound` interval a pool may set, so that auto-compounding uses only a
+/// few of a position's checkpoints per retention window.
+pub const MIN_COMPOUND_INTERVAL: i64 = 86_400;
+
+/// Basis-point denominator for boost multipliers.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Fixed-point scale for `StakePool::acc_reward_per_weight`.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Calls `sync_voting_power` with the history and delegation accounts of a context
+/// that moves a position's stake.
+macro_rules! sync_position_power {
+    ($accounts:expr, $user_stake:expr, $clock:expr) => {
+        sync_voting_power(
+            $user_stake,
+            $clock,
+            &mut $accounts.user_history,
+            &mut $accounts.pool_history,
+            $accounts.delegation.as_deref_mut().map(|d| &mut **d),
+            $accounts.delegatee_history.as_deref_mut().map(|h| &mut **h),
+        )
+    };
+}
+
 #[program]
 pub mod grit_staking {
     use super::*;
 
-    pub fn initialize(_ctx: Context<Initialize>) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.grit_mint = ctx.accounts.grit_mint.key();
+        config.moxy_mint = ctx.accounts.moxy_mint.key();
+        config.chi_mint = ctx.accounts.chi_mint.key();
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Pins the v0 stake vault that `migrate_stake` draws principal from.
+    pub fn set_legacy_vault(ctx: Context<SetLegacyVault>) -> Result<()> {
+        ctx.accounts.config.legacy_vault = ctx.accounts.legacy_vault.key();
+        Ok(())
+    }
+
+    pub fn create_pool(ctx: Context<CreatePool>, schedule: EmissionSchedule, lockup_duration: i64) -> Result<()> {
+        require!(lockup_duration >= 0, ErrorCode::InvalidLockup);
+        require!(schedule.halving_interval >= 0, ErrorCode::InvalidSchedule);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.authority.key();
+        pool.stake_mint = ctx.accounts.stake_mint.key();
+        pool.reward_mint = ctx.accounts.reward_mint.key();
+        pool.vault = ctx.accounts.vault.key();
+        pool.reward_vault = ctx.accounts.reward_vault.key();
+        pool.schedule = schedule;
+        pool.lockup_duration = lockup_duration;
+        pool.total_staked = 0;
+        pool.total_weight = 0;
+        pool.acc_reward_per_weight = 0;
+        pool.last_update_time = Clock::get()?.unix_timestamp;
+        pool.bump = ctx.bumps.pool;
+
+        let pool_history = &mut ctx.accounts.pool_history;
+        pool_history.pool = pool.key();
+        pool_history.bump = ctx.bumps.pool_history;
+        Ok(())
+    }
+
+    pub fn set_pool_params(ctx: Context<SetPoolParams>, lockup_duration: i64) -> Result<()> {
+        require!(lockup_duration >= 0, ErrorCode::InvalidLockup);
+        ctx.accounts.pool.lockup_duration = lockup_duration;
+        Ok(())
+    }
+
+    pub fn set_guardian(ctx: Context<SetPoolParams>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.pool.guardian = guardian;
+        Ok(())
+    }
+
+    /// Halts staking, claiming and compounding (or resumes them). While paused,
+    /// stakers can still leave through `emergency_withdraw`.
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        if pool.paused == paused {
+            return Ok(());
+        }
+        pool.paused = paused;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        if paused {
+            emit!(PoolPaused { pool: pool.key(), guardian: ctx.accounts.guardian.key(), timestamp });
+        } else {
+            emit!(PoolUnpaused { pool: pool.key(), guardian: ctx.accounts.guardian.key(), timestamp });
+        }
+        Ok(())
+    }
+
+    /// Starts a new emission segment at `now` with a fresh rate and halving interval,
+    /// adding `additional_budget` to the schedule's total.
+    pub fn extend_schedule(
+        ctx: Context<SetPoolParams>,
+        additional_budget: u64,
+        initial_rate: u64,
+        halving_interval: i64,
+    ) -> Result<()> {
+        require!(halving_interval >= 0, ErrorCode::InvalidSchedule);
+
+        let pool = &mut ctx.accounts.pool;
+        let now = Clock::get()?.unix_timestamp;
+        // Accrue under the old schedule before switching.
+        pool.update(now)?;
+        pool.schedule = EmissionSchedule {
+            start_time: now,
+            initial_rate,
+            halving_interval,
+            budget: pool.schedule.budget.checked_add(additional_budget).ok_or(ErrorCode::MathOverflow)?,
+        };
+        Ok(())
+    }
+
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.total_funded = pool.total_funded.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
         Ok(())
     }
 
     pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, ErrorCode::PoolPaused);
         let user_stake = &mut ctx.accounts.user_stake;
         let clock = Clock::get()?;
 
+        pool.update(clock.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
         if user_stake.amount == 0 {
             user_stake.start_time = clock.unix_timestamp;
             user_stake.owner = ctx.accounts.user.key();
+            user_stake.pool = pool.key();
+            user_stake.bump = ctx.bumps.user_stake;
+            user_stake.version = USER_STAKE_VERSION;
+
+            let history = &mut ctx.accounts.user_history;
+            history.owner = ctx.accounts.user.key();
+            history.pool = pool.key();
+            history.bump = ctx.bumps.user_history;
         }
-        
-        user_stake.amount = user_stake.amount.checked_add(amount).unwrap();
+
+        user_stake.amount = user_stake.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.lock_end = clock.unix_timestamp.checked_add(pool.lockup_duration).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.lock_duration = pool.lockup_duration;
+        pool.total_staked = pool.total_staked.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.reweight(pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
 
         // CPI to transfer tokens to vault
         let cpi_accounts = Transfer {
             from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.stake_vault.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
             authority: ctx.accounts.user.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
@@ -35,113 +225,3329 @@ pub mod grit_staking {
     }
 
     pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
         let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
 
         require!(user_stake.amount >= amount, ErrorCode::InsufficientFunds);
+        require!(clock.unix_timestamp >= user_stake.lock_end, ErrorCode::StakeLocked);
+
+        pool.update(clock.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
 
-        user_stake.amount = user_stake.amount.checked_sub(amount).unwrap();
+        user_stake.amount = user_stake.amount.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool.total_staked.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.reweight(pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
 
-        // CPI to transfer tokens back to user
-        // Note: Real impl needs PDA signer for vault
+        // CPI to transfer tokens back to user, signed by the pool PDA
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
         let cpi_accounts = Transfer {
-            from: ctx.accounts.stake_vault.to_account_info(),
+            from: ctx.accounts.vault.to_account_info(),
             to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.stake_vault_authority.to_account_info(),
+            authority: pool.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        Ok(())
+    }
+
+    /// Returns a position's full principal while the pool is paused, ignoring any
+    /// lock. Rewards are not settled: anything accrued since the last settlement is
+    /// forfeited, so the suspect reward math never runs.
+    pub fn emergency_withdraw(ctx: Context<Unstake>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(pool.paused, ErrorCode::PoolNotPaused);
+
+        let amount = user_stake.release_all(pool)?;
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        let clock = Clock::get()?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)?;
+
+        emit!(EmergencyWithdrawal {
+            pool: pool.key(),
+            user: user_stake.owner,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Leaves a locked position before `lock_end`, paying a penalty that decays
+    /// linearly to zero at expiry. The penalty is split between an Ascesis burn and
+    /// the treasury according to the pool's penalty configuration.
+    pub fn early_exit(ctx: Context<EarlyExit>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        require!(user_stake.amount >= amount, ErrorCode::InsufficientFunds);
+        require!(now < user_stake.lock_end, ErrorCode::StakeNotLocked);
+
+        pool.update(now)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        let (burned, to_treasury) = early_exit_penalty(
+            amount,
+            user_stake.lock_end - now,
+            user_stake.lock_duration,
+            pool.max_exit_penalty_bps,
+            pool.penalty_burn_bps,
+        )?;
+        let returned = amount - burned - to_treasury;
+
+        user_stake.amount = user_stake.amount.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool.total_staked.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.reweight(pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        if burned > 0 {
+            let cpi_accounts = token::Burn {
+                mint: ctx.accounts.stake_mint.to_account_info(),
+                from: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::burn(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), burned)?;
+
+            let record = &mut ctx.accounts.ascesis_record;
+            if record.burn_count == 0 {
+                record.owner = ctx.accounts.user.key();
+                record.mint = pool.stake_mint;
+                record.bump = ctx.bumps.ascesis_record;
+            }
+            record.record_burn(burned, now)?;
+        }
+
+        if to_treasury > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), to_treasury)?;
+        }
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), returned)?;
+
+        msg!("Early exit: {} returned, {} burned, {} to treasury", returned, burned, to_treasury);
+        Ok(())
+    }
+
+    pub fn set_penalty_config(
+        ctx: Context<SetPenaltyConfig>,
+        max_exit_penalty_bps: u16,
+        penalty_burn_bps: u16,
+    ) -> Result<()> {
+        require!(max_exit_penalty_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+        require!(penalty_burn_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.max_exit_penalty_bps = max_exit_penalty_bps;
+        pool.penalty_burn_bps = penalty_burn_bps;
+        pool.penalty_treasury = ctx.accounts.treasury_token_account.key();
+        Ok(())
+    }
 
+    pub fn set_slash_config(
+        ctx: Context<SetSlashConfig>,
+        slash_authority: Pubkey,
+        max_slash_bps_per_epoch: u16,
+        slash_burn_bps: u16,
+    ) -> Result<()> {
+        require!(max_slash_bps_per_epoch as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+        require!(slash_burn_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.slash_authority = slash_authority;
+        pool.max_slash_bps_per_epoch = max_slash_bps_per_epoch;
+        pool.slash_burn_bps = slash_burn_bps;
+        Ok(())
+    }
+
+    /// Takes `bps` of `user`'s position as a penalty for misbehaviour, burning part
+    /// of it and sending the rest to the treasury. Only the pool's slashing authority
+    /// (typically a governance PDA signing via CPI) may call this.
+    pub fn slash(ctx: Context<Slash>, bps: u16, reason_hash: [u8; 32]) -> Result<()> {
+        require!(bps > 0 && bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        pool.update(clock.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        let amount = u64::try_from((user_stake.amount as u128) * (bps as u128) / BPS_DENOMINATOR as u128)
+            .map_err(|_| ErrorCode::MathOverflow)?;
+        require!(amount > 0, ErrorCode::ZeroAmount);
+        pool.charge_slash(clock.epoch, amount)?;
+
+        let burned = u64::try_from((amount as u128) * (pool.slash_burn_bps as u128) / BPS_DENOMINATOR as u128)
+            .map_err(|_| ErrorCode::MathOverflow)?;
+        let to_treasury = amount - burned;
+
+        user_stake.amount = user_stake.amount.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool.total_staked.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.reweight(pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
+
+        let record = &mut ctx.accounts.slash_record;
+        record.pool = pool.key();
+        record.user = user_stake.owner;
+        record.authority = ctx.accounts.slash_authority.key();
+        record.amount = amount;
+        record.burned = burned;
+        record.bps = bps;
+        record.reason_hash = reason_hash;
+        record.timestamp = clock.unix_timestamp;
+        record.bump = ctx.bumps.slash_record;
+        pool.slash_count = pool.slash_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        if burned > 0 {
+            let cpi_accounts = token::Burn {
+                mint: ctx.accounts.stake_mint.to_account_info(),
+                from: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::burn(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), burned)?;
+        }
+
+        if to_treasury > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), to_treasury)?;
+        }
+
+        msg!("Slashed {}: {} burned, {} to treasury", amount, burned, to_treasury);
         Ok(())
     }
 
     pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, ErrorCode::PoolPaused);
         let user_stake = &mut ctx.accounts.user_stake;
         let clock = Clock::get()?;
-        
-        let seconds_staked = clock.unix_timestamp - user_stake.start_time;
-        // Mock reward: 1 token per second
-        let reward = seconds_staked as u64; 
+
+        pool.update(clock.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        let reward = user_stake.pending_rewards;
+        require!(
+            ctx.accounts.reward_vault.amount >= reward,
+            ErrorCode::InsufficientRewardFunds
+        );
+        user_stake.pending_rewards = 0;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.user_reward_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), reward)?;
 
         msg!("Claimed {} tokens as reward", reward);
-        
-        // Reset timer logic needed in real impl
-        
+
         Ok(())
     }
 
-    pub fn burn_for_ascesis(ctx: Context<BurnForAscesis>, amount: u64) -> Result<()> {
-        let cpi_accounts = token::Burn {
-            mint: ctx.accounts.mint.to_account_info(),
+    pub fn set_compound_config(ctx: Context<SetCompoundConfig>, compound_interval: i64, compound_bounty_bps: u16) -> Result<()> {
+        require!(compound_interval >= MIN_COMPOUND_INTERVAL, ErrorCode::CompoundIntervalTooShort);
+        require!(compound_bounty_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBps);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.compound_interval = compound_interval;
+        pool.compound_bounty_bps = compound_bounty_bps;
+        Ok(())
+    }
+
+    pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+        if enabled {
+            require_keys_eq!(
+                ctx.accounts.pool.reward_mint,
+                ctx.accounts.pool.stake_mint,
+                ErrorCode::CompoundRequiresSameMint
+            );
+        }
+        ctx.accounts.user_stake.auto_compound = enabled;
+        Ok(())
+    }
+
+    /// Permissionless crank: restakes a position's rewards and pays the caller a bounty.
+    pub fn compound(ctx: Context<Compound>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, ErrorCode::PoolPaused);
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+
+        require!(user_stake.auto_compound, ErrorCode::AutoCompoundDisabled);
+        require_keys_eq!(pool.reward_mint, pool.stake_mint, ErrorCode::CompoundRequiresSameMint);
+        require!(
+            now >= user_stake.last_compound_time.saturating_add(pool.compound_interval),
+            ErrorCode::CompoundTooSoon
+        );
+        // A crank cannot strip boosters by omitting proofs; it must prove every one.
+        let held = held_boosters(user_stake, ctx.remaining_accounts);
+        require!(
+            held[..user_stake.booster_count as usize].iter().all(|h| *h),
+            ErrorCode::BoosterNotHeld
+        );
+
+        pool.update(now)?;
+        user_stake.settle(pool.acc_reward_per_weight)?;
+
+        let reward = user_stake.pending_rewards;
+        require!(reward > 0, ErrorCode::ZeroAmount);
+        require!(
+            ctx.accounts.reward_vault.amount >= reward,
+            ErrorCode::InsufficientRewardFunds
+        );
+        let (restaked, bounty) = split_bounty(reward, pool.compound_bounty_bps)?;
+
+        // Restaking keeps `start_time` and `lock_end` untouched.
+        user_stake.pending_rewards = 0;
+        user_stake.amount = user_stake.amount.checked_add(restaked).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.last_compound_time = now;
+        pool.total_staked = pool.total_staked.checked_add(restaked).ok_or(ErrorCode::MathOverflow)?;
+        user_stake.reweight(pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), restaked)?;
+
+        if bounty > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.caller_reward_account.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), bounty)?;
+        }
+
+        msg!("Compounded {} tokens for {} ({} bounty)", restaked, user_stake.owner, bounty);
+        Ok(())
+    }
+
+    pub fn set_boost_config(
+        ctx: Context<SetBoostConfig>,
+        collection: Pubkey,
+        boost_bps: [u16; RARITY_TIERS],
+        max_boost_bps: u16,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.boost_collection = collection;
+        pool.boost_bps = boost_bps;
+        pool.max_boost_bps = max_boost_bps;
+        Ok(())
+    }
+
+    pub fn register_booster(ctx: Context<RegisterBooster>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let nft_mint = ctx.accounts.nft_mint.key();
+
+        require_keys_neq!(pool.boost_collection, Pubkey::default(), ErrorCode::BoostsDisabled);
+        require!(
+            is_verified_member(&ctx.accounts.nft_metadata, &pool.boost_collection),
+            ErrorCode::InvalidCollection
+        );
+        require!(
+            booster_held(&ctx.accounts.booster_proof, &user_stake.owner, &nft_mint),
+            ErrorCode::BoosterNotHeld
+        );
+
+        pool.update(Clock::get()?.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        user_stake.add_booster(Booster { nft_mint, tier: ctx.accounts.rarity_entry.tier })?;
+        user_stake.reweight(pool)?;
+        Ok(())
+    }
+
+    pub fn remove_booster(ctx: Context<RemoveBooster>, nft_mint: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+
+        pool.update(Clock::get()?.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        let mut keep = [false; MAX_BOOSTERS];
+        for (i, booster) in user_stake.active_boosters().iter().enumerate() {
+            keep[i] = booster.nft_mint != nft_mint;
+        }
+        user_stake.retain_boosters(&keep);
+        user_stake.reweight(pool)?;
+        Ok(())
+    }
+
+    pub fn enable_liquid_staking(ctx: Context<EnableLiquidStaking>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        // Compounding only makes sense when rewards are paid in the staked token.
+        require_keys_eq!(pool.reward_mint, pool.stake_mint, ErrorCode::LiquidRequiresSameMint);
+        require_keys_eq!(pool.receipt_mint, Pubkey::default(), ErrorCode::LiquidAlreadyEnabled);
+
+        pool.receipt_mint = ctx.accounts.receipt_mint.key();
+        Ok(())
+    }
+
+    pub fn stake_liquid(ctx: Context<StakeLiquid>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+
+        let pool = &mut ctx.accounts.pool;
+        require!(!pool.paused, ErrorCode::PoolPaused);
+        pool.update(Clock::get()?.unix_timestamp)?;
+        let compounded = pool.compound_liquid(ctx.accounts.reward_vault.amount)?;
+
+        let shares = shares_for_deposit(amount, pool.liquid_staked, ctx.accounts.receipt_mint.supply)?;
+        require!(shares > 0, ErrorCode::ZeroAmount);
+        pool.add_liquid(amount)?;
+
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        if compounded > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), compounded)?;
+        }
+
+        let cpi_accounts = Transfer {
             from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
             authority: ctx.accounts.user.to_account_info(),
         };
+        token::transfer(CpiContext::new(cpi_program.clone(), cpi_accounts), amount)?;
+
+        let cpi_accounts = token::MintTo {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            to: ctx.accounts.user_receipt_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), shares)?;
+
+        Ok(())
+    }
+
+    pub fn redeem(ctx: Context<Redeem>, shares: u64) -> Result<()> {
+        require!(shares > 0, ErrorCode::ZeroAmount);
+
+        let pool = &mut ctx.accounts.pool;
+        let now = Clock::get()?.unix_timestamp;
+        pool.update(now)?;
+        let compounded = pool.compound_liquid(ctx.accounts.reward_vault.amount)?;
+
+        let underlying = underlying_for_shares(shares, pool.liquid_staked, ctx.accounts.receipt_mint.supply)?;
+        pool.remove_liquid(underlying)?;
+
+        // Redeemed tokens stay in the vault until the unbonding period ends.
+        let ticket = &mut ctx.accounts.redeem_ticket;
+        ticket.owner = ctx.accounts.user.key();
+        ticket.pool = pool.key();
+        ticket.amount = ticket.amount.checked_add(underlying).ok_or(ErrorCode::MathOverflow)?;
+        ticket.release_time = now.checked_add(pool.lockup_duration).ok_or(ErrorCode::MathOverflow)?;
+        ticket.bump = ctx.bumps.redeem_ticket;
+
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::burn(cpi_ctx, amount)?;
+        if compounded > 0 {
+            let seeds = pool.signer_seeds();
+            let signer = &[&seeds[..]];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer), compounded)?;
+        }
+
+        let cpi_accounts = token::Burn {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            from: ctx.accounts.user_receipt_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::burn(CpiContext::new(cpi_program, cpi_accounts), shares)?;
 
-        msg!("Ascesis Burn: {} tokens sacrificed by {}", amount, ctx.accounts.user.key());
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize {}
+    pub fn withdraw_redeemed(ctx: Context<WithdrawRedeemed>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let ticket = &ctx.accounts.redeem_ticket;
+        require!(
+            Clock::get()?.unix_timestamp >= ticket.release_time,
+            ErrorCode::StakeLocked
+        );
 
-#[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(init_if_needed, payer = user, space = 8 + 32 + 8 + 8, seeds = [b"stake", user.key().as_ref()], bump)]
-    pub user_stake: Account<'info, UserStake>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let seeds = pool.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), ticket.amount)?;
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut, seeds = [b"stake", user.key().as_ref()], bump)]
-    pub user_stake: Account<'info, UserStake>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
-    /// CHECK: PDA signer
-    pub stake_vault_authority: AccountInfo<'info>,
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ClaimReward<'info> {
-    #[account(mut, seeds = [b"stake", user.key().as_ref()], bump)]
-    pub user_stake: Account<'info, UserStake>,
-    pub user: Signer<'info>,
-}
+    pub fn create_rarity_table(ctx: Context<CreateRarityTable>, rates: [u64; RARITY_TIERS]) -> Result<()> {
+        let table = &mut ctx.accounts.rarity_table;
+        table.authority = ctx.accounts.authority.key();
+        table.collection = ctx.accounts.collection_mint.key();
+        table.reward_vault = ctx.accounts.nft_reward_vault.key();
+        table.rates = rates;
+        table.last_accrual_time = Clock::get()?.unix_timestamp;
+        table.bump = ctx.bumps.rarity_table;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct BurnForAscesis<'info> {
-    #[account(mut)]
-    pub mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+    /// New rates apply from now on; time already staked keeps accruing at the old ones.
+    pub fn set_rarity_rates(ctx: Context<SetRarityRates>, rates: [u64; RARITY_TIERS]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.rarity_table.set_rates(rates, now)
+    }
 
-#[account]
-pub struct UserStake {
-    pub owner: Pubkey,
-    pub amount: u64,
-    pub start_time: i64,
-}
+    pub fn set_nft_rarity(ctx: Context<SetNftRarity>, tier: RarityTier) -> Result<()> {
+        let entry = &mut ctx.accounts.rarity_entry;
+        entry.nft_mint = ctx.accounts.nft_mint.key();
+        entry.tier = tier;
+        entry.bump = ctx.bumps.rarity_entry;
+        Ok(())
+    }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Insufficient staked funds.")]
-    InsufficientFunds,
+    pub fn fund_nft_rewards(ctx: Context<FundNftRewards>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.nft_reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+        Ok(())
+    }
+
+    pub fn stake_nft(ctx: Context<StakeNft>) -> Result<()> {
+        require!(
+            is_verified_member(&ctx.accounts.nft_metadata, &ctx.accounts.rarity_table.collection),
+            ErrorCode::InvalidCollection
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let nft_stake = &mut ctx.accounts.nft_stake;
+        nft_stake.owner = ctx.accounts.user.key();
+        nft_stake.nft_mint = ctx.accounts.nft_mint.key();
+        nft_stake.rarity_table = ctx.accounts.rarity_table.key();
+        nft_stake.tier = ctx.accounts.rarity_entry.tier;
+        nft_stake.start_time = now;
+        nft_stake.reward_debt = ctx.accounts.rarity_table.accrued_at(nft_stake.tier, now)?;
+        nft_stake.owed = 0;
+        nft_stake.bump = ctx.bumps.nft_stake;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_nft_account.to_account_info(),
+            to: ctx.accounts.nft_escrow.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), 1)?;
+
+        Ok(())
+    }
+
+    pub fn claim_nft_reward(ctx: Context<ClaimNftReward>) -> Result<()> {
+        let table = &ctx.accounts.rarity_table;
+        let nft_stake = &mut ctx.accounts.nft_stake;
+        let now = Clock::get()?.unix_timestamp;
+
+        let accrued = table.accrued_at(nft_stake.tier, now)?;
+        nft_stake.settle(accrued)?;
+        let reward = nft_stake.take_payout(ctx.accounts.nft_reward_vault.amount);
+        require!(reward > 0, ErrorCode::InsufficientRewardFunds);
+
+        let collection = table.collection;
+        let bump = [table.bump];
+        let seeds: &[&[u8]] = &[b"rarity_table", collection.as_ref(), &bump];
+        let signer = &[seeds];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.nft_reward_vault.to_account_info(),
+            to: ctx.accounts.user_reward_account.to_account_info(),
+            authority: table.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), reward)?;
+
+        msg!("Claimed {} tokens for staked NFT {}, {} still owed", reward, nft_stake.nft_mint, nft_stake.owed);
+        Ok(())
+    }
+
+    /// Always returns the NFT. The reward is paid up to what the vault holds; anything
+    /// the vault cannot cover is forfeited, so holders of an underfunded collection
+    /// should claim once it is refilled before unstaking.
+    pub fn unstake_nft(ctx: Context<UnstakeNft>) -> Result<()> {
+        let table = &ctx.accounts.rarity_table;
+        let nft_stake = &mut ctx.accounts.nft_stake;
+        let now = Clock::get()?.unix_timestamp;
+
+        let accrued = table.accrued_at(nft_stake.tier, now)?;
+        nft_stake.settle(accrued)?;
+        let reward = nft_stake.take_payout(ctx.accounts.nft_reward_vault.amount);
+        let forfeited = nft_stake.owed;
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        if reward > 0 {
+            let collection = table.collection;
+            let table_bump = [table.bump];
+            let table_seeds: &[&[u8]] = &[b"rarity_table", collection.as_ref(), &table_bump];
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.nft_reward_vault.to_account_info(),
+                to: ctx.accounts.user_reward_account.to_account_info(),
+                authority: table.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, &[table_seeds]),
+                reward,
+            )?;
+        }
+
+        // Return the NFT and close the escrow, both signed by the stake PDA.
+        let nft_mint = nft_stake.nft_mint;
+        let stake_bump = [nft_stake.bump];
+        let stake_seeds: &[&[u8]] = &[b"nft_stake", nft_mint.as_ref(), &stake_bump];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.nft_escrow.to_account_info(),
+            to: ctx.accounts.user_nft_account.to_account_info(),
+            authority: nft_stake.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, &[stake_seeds]),
+            1,
+        )?;
+
+        let cpi_accounts = token::CloseAccount {
+            account: ctx.accounts.nft_escrow.to_account_info(),
+            destination: ctx.accounts.user.to_account_info(),
+            authority: nft_stake.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, &[stake_seeds]))?;
+
+        msg!("Unstaked NFT {} with {} tokens reward, {} forfeited", nft_mint, reward, forfeited);
+        Ok(())
+    }
+
+    /// Moves a v0 position, kept at `[b"stake", owner]` before pools existed, into the
+    /// owner's position in `pool`. Its principal is transferred from the v0 stake vault
+    /// pinned in `StakingConfig`, whose authority must sign alongside the owner, and the
+    /// v0 account is closed to the owner. Its
+    /// `start_time` is kept, so the position does not lose its Heritage age.
+    pub fn migrate_stake(ctx: Context<MigrateStake>) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_stake.to_account_info();
+        let legacy = read_v0_stake(&legacy_info.try_borrow_data()?)?;
+        require_keys_eq!(legacy.owner, ctx.accounts.owner.key(), ErrorCode::InvalidStakeAccount);
+
+        let pool = &mut ctx.accounts.pool;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+
+        pool.update(clock.unix_timestamp)?;
+        settle_position(pool, user_stake, ctx.remaining_accounts)?;
+
+        if user_stake.amount == 0 {
+            user_stake.owner = legacy.owner;
+            user_stake.pool = pool.key();
+            user_stake.bump = ctx.bumps.user_stake;
+            user_stake.version = USER_STAKE_VERSION;
+
+            let history = &mut ctx.accounts.user_history;
+            history.owner = legacy.owner;
+            history.pool = pool.key();
+            history.bump = ctx.bumps.user_history;
+        }
+        user_stake.absorb_v0(&legacy, pool)?;
+        sync_position_power!(ctx.accounts, user_stake, &clock)?;
+
+        if legacy.amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.legacy_vault.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.legacy_vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), legacy.amount)?;
+        }
+
+        // Close the v0 account, returning its rent to the owner.
+        let owner_info = ctx.accounts.owner.to_account_info();
+        let rent = legacy_info.lamports();
+        **owner_info.try_borrow_mut_lamports()? = owner_info.lamports().checked_add(rent).ok_or(ErrorCode::MathOverflow)?;
+        **legacy_info.try_borrow_mut_lamports()? = 0;
+        legacy_info.assign(&system_program::ID);
+        legacy_info.realloc(0, false)?;
+
+        msg!("Migrated v0 stake of {}: {} tokens", legacy.owner, legacy.amount);
+        Ok(())
+    }
+
+    /// Mints a soulbound Token-2022 badge to a staker whose position has been held
+    /// for `HERITAGE_MILESTONE_DAYS[milestone]` days. Anyone may crank this; each
+    /// position can receive each badge once, since the badge mint is a PDA of both.
+    pub fn mint_heritage_badge(ctx: Context<MintHeritageBadge>, milestone: u8) -> Result<()> {
+        let user_stake = &ctx.accounts.user_stake;
+        require!(user_stake.amount > 0, ErrorCode::StakeInactive);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            heritage_milestone_reached(user_stake.start_time, now, milestone)?,
+            ErrorCode::MilestoneNotReached
+        );
+        let days = HERITAGE_MILESTONE_DAYS[milestone as usize];
+
+        let pool = &ctx.accounts.pool;
+        let badge_mint = ctx.accounts.badge_mint.to_account_info();
+        let pool_info = pool.to_account_info();
+        let token_program_id = ctx.accounts.token_program.key();
+
+        let pool_seeds = pool.signer_seeds();
+        let user_stake_key = user_stake.key();
+        let milestone_seed = [milestone];
+        let mint_bump = [ctx.bumps.badge_mint];
+        let mint_seeds: &[&[u8]] = &[b"heritage_badge", user_stake_key.as_ref(), &milestone_seed, &mint_bump];
+
+        // Allocate the mint for its extensions, but fund it for the metadata the token
+        // program appends when it is initialized.
+        let metadata = TokenMetadata {
+            name: heritage_badge_name(days),
+            symbol: HERITAGE_BADGE_SYMBOL.to_string(),
+            ..Default::default()
+        };
+        let mint_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::NonTransferable,
+            ExtensionType::MetadataPointer,
+        ])?;
+        let lamports = Rent::get()?.minimum_balance(mint_len + metadata.tlv_size_of()?);
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount { from: ctx.accounts.payer.to_account_info(), to: badge_mint.clone() },
+                &[mint_seeds],
+            ),
+            lamports,
+            mint_len as u64,
+            &token_program_id,
+        )?;
+
+        invoke(
+            &spl_token_2022::instruction::initialize_non_transferable_mint(&token_program_id, badge_mint.key)?,
+            std::slice::from_ref(&badge_mint),
+        )?;
+        invoke(
+            &metadata_pointer::instruction::initialize(
+                &token_program_id,
+                badge_mint.key,
+                Some(pool.key()),
+                Some(badge_mint.key()),
+            )?,
+            std::slice::from_ref(&badge_mint),
+        )?;
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_2022::InitializeMint2 { mint: badge_mint.clone() },
+            ),
+            0,
+            &pool.key(),
+            None,
+        )?;
+        invoke_signed(
+            &spl_token_metadata_interface::instruction::initialize(
+                &token_program_id,
+                badge_mint.key,
+                &pool.key(),
+                badge_mint.key,
+                &pool.key(),
+                metadata.name,
+                metadata.symbol,
+                metadata.uri,
+            ),
+            &[badge_mint.clone(), pool_info.clone()],
+            &[&pool_seeds[..]],
+        )?;
+
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.badge_token_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+                mint: badge_mint.clone(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = token_2022::MintTo {
+            mint: badge_mint.clone(),
+            to: ctx.accounts.badge_token_account.to_account_info(),
+            authority: pool_info.clone(),
+        };
+        token_2022::mint_to(CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, &[&pool_seeds[..]]), 1)?;
+
+        // Fix the supply at one.
+        let cpi_accounts = token_2022::SetAuthority { current_authority: pool_info, account_or_mint: badge_mint };
+        token_2022::set_authority(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, &[&pool_seeds[..]]),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        msg!("Heritage badge: {} days for {}", days, ctx.accounts.owner.key());
+        Ok(())
+    }
+
+    /// Permissionless crank: recomputes a position's voting power for its current
+    /// Heritage age, which otherwise only moves when the position's stake does.
+    pub fn refresh_voting_power(ctx: Context<RefreshVotingPower>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(ctx.accounts.user_history.refresh_due(clock.slot), ErrorCode::RefreshTooSoon);
+        let user_stake = &mut ctx.accounts.user_stake;
+        sync_position_power!(ctx.accounts, user_stake, &clock)
+    }
+
+    /// Staked weight recorded in `history` at `slot`, returned to CPI callers.
+    pub fn weight_at(ctx: Context<WeightAt>, slot: u64) -> Result<u64> {
+        Ok(ctx.accounts.history.weight_at(slot))
+    }
+
+    /// Points this position's voting power at `delegatee`, replacing any existing
+    /// delegation. Tokens stay in the position; only the votes move. The delegatee
+    /// must sign to accept.
+    pub fn delegate(ctx: Context<Delegate>) -> Result<()> {
+        let delegatee = ctx.accounts.delegatee.key();
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(delegatee != user_stake.owner, ErrorCode::InvalidDelegatee);
+        require!(delegatee != user_stake.delegatee, ErrorCode::InvalidDelegatee);
+
+        let clock = Clock::get()?;
+        let old_power = user_stake.voting_power;
+        let new_power = voting_power(user_stake.amount, clock.unix_timestamp.saturating_sub(user_stake.start_time))?;
+
+        if user_stake.delegatee == Pubkey::default() {
+            let user_history = &mut ctx.accounts.user_history;
+            user_history.power = user_history.power.checked_sub(old_power).ok_or(ErrorCode::MathOverflow)?;
+            user_history.checkpoint(clock.slot)?;
+        } else {
+            let previous = ctx
+                .accounts
+                .previous_delegatee_history
+                .as_deref_mut()
+                .ok_or(ErrorCode::DelegationRequired)?;
+            require!(
+                previous.owner == user_stake.delegatee && previous.pool == user_stake.pool,
+                ErrorCode::InvalidDelegation
+            );
+            previous.delegated_power = previous.delegated_power.checked_sub(old_power).ok_or(ErrorCode::MathOverflow)?;
+            previous.checkpoint(clock.slot)?;
+        }
+
+        let delegatee_history = &mut ctx.accounts.delegatee_history;
+        if delegatee_history.owner == Pubkey::default() {
+            delegatee_history.owner = delegatee;
+            delegatee_history.pool = user_stake.pool;
+            delegatee_history.bump = ctx.bumps.delegatee_history;
+        }
+        delegatee_history.delegated_power =
+            delegatee_history.delegated_power.checked_add(new_power).ok_or(ErrorCode::MathOverflow)?;
+        delegatee_history.checkpoint(clock.slot)?;
+
+        let pool_history = &mut ctx.accounts.pool_history;
+        pool_history.power = shift(pool_history.power, old_power, new_power)?;
+        pool_history.checkpoint(clock.slot)?;
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.delegator = user_stake.owner;
+        delegation.pool = user_stake.pool;
+        delegation.delegatee = delegatee;
+        delegation.power = new_power;
+        delegation.bump = ctx.bumps.delegation;
+
+        user_stake.delegatee = delegatee;
+        user_stake.voting_power = new_power;
+        Ok(())
+    }
+
+    /// Revokes a delegation, returning the position's voting power to its owner.
+    pub fn undelegate(ctx: Context<Undelegate>) -> Result<()> {
+        let user_stake = &mut ctx.accounts.user_stake;
+        let clock = Clock::get()?;
+        let old_power = user_stake.voting_power;
+        let new_power = voting_power(user_stake.amount, clock.unix_timestamp.saturating_sub(user_stake.start_time))?;
+
+        let delegatee_history = &mut ctx.accounts.delegatee_history;
+        delegatee_history.delegated_power =
+            delegatee_history.delegated_power.checked_sub(old_power).ok_or(ErrorCode::MathOverflow)?;
+        delegatee_history.checkpoint(clock.slot)?;
+
+        let user_history = &mut ctx.accounts.user_history;
+        user_history.power = user_history.power.checked_add(new_power).ok_or(ErrorCode::MathOverflow)?;
+        user_history.checkpoint(clock.slot)?;
+
+        let pool_history = &mut ctx.accounts.pool_history;
+        pool_history.power = shift(pool_history.power, old_power, new_power)?;
+        pool_history.checkpoint(clock.slot)?;
+
+        user_stake.delegatee = Pubkey::default();
+        user_stake.voting_power = new_power;
+        Ok(())
+    }
+
+    pub fn burn_for_ascesis(ctx: Context<BurnForAscesis>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+
+        let cpi_accounts = token::Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::burn(cpi_ctx, amount)?;
+
+        let record = &mut ctx.accounts.ascesis_record;
+        if record.burn_count == 0 {
+            record.owner = ctx.accounts.user.key();
+            record.mint = ctx.accounts.mint.key();
+            record.bump = ctx.bumps.ascesis_record;
+        }
+        record.record_burn(amount, Clock::get()?.unix_timestamp)?;
+
+        msg!("Ascesis Burn: {} tokens sacrificed by {}", amount, ctx.accounts.user.key());
+        Ok(())
+    }
+
+    pub fn configure_ring(
+        ctx: Context<ConfigureRing>,
+        ring: u8,
+        required_burns: [u64; 3],
+        min_burn_count: u64,
+    ) -> Result<()> {
+        require!(ring > 0, ErrorCode::InvalidRing);
+
+        let ascesis_ring = &mut ctx.accounts.ascesis_ring;
+        ascesis_ring.index = ring;
+        ascesis_ring.required_burns = required_burns;
+        ascesis_ring.min_burn_count = min_burn_count;
+        ascesis_ring.bump = ctx.bumps.ascesis_ring;
+        Ok(())
+    }
+
+    pub fn advance_ring(ctx: Context<AdvanceRing>, ring: u8, token_type: TokenType) -> Result<()> {
+        let progress = &mut ctx.accounts.ring_progress;
+        let record = &mut ctx.accounts.ascesis_record;
+        let amount = ctx.accounts.ascesis_ring.burn_required(progress.ring_level, ring, record.burn_count, token_type)?;
+
+        let cpi_accounts = token::Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::burn(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if record.burn_count == 0 {
+            record.owner = ctx.accounts.user.key();
+            record.mint = ctx.accounts.mint.key();
+            record.bump = ctx.bumps.ascesis_record;
+        }
+        record.record_burn(amount, now)?;
+
+        if progress.ring_level == 0 {
+            progress.owner = ctx.accounts.user.key();
+            progress.bump = ctx.bumps.ring_progress;
+        }
+        progress.ring_level = ring;
+        progress.last_advance_time = now;
+
+        emit!(RingAdvanced {
+            user: ctx.accounts.user.key(),
+            ring,
+            token_type,
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+// --- PURE LOGIC HELPERS ---
+
+/// Whether a position opened at `start_time` has reached Heritage `milestone` by `now`.
+pub fn heritage_milestone_reached(start_time: i64, now: i64, milestone: u8) -> Result<bool> {
+    let days = *HERITAGE_MILESTONE_DAYS
+        .get(milestone as usize)
+        .ok_or(ErrorCode::InvalidMilestone)?;
+    Ok(now.saturating_sub(start_time) >= days * 86_400)
+}
+
+pub fn heritage_badge_name(days: i64) -> String {
+    format!("GRIT Heritage: {} Days", days)
+}
+
+/// Decodes a v0 `UserStake` account, discriminator included.
+pub fn read_v0_stake(data: &[u8]) -> Result<UserStakeV0> {
+    require!(data.len() == UserStakeV0::LEN, ErrorCode::InvalidStakeAccount);
+    require!(data[..8] == UserStake::DISCRIMINATOR, ErrorCode::InvalidStakeAccount);
+    UserStakeV0::deserialize(&mut &data[8..]).map_err(|_| error!(ErrorCode::InvalidStakeAccount))
+}
+
+/// Heritage time multiplier in basis points: +10% per 30 days staked, capped at 2.5x.
+pub fn heritage_multiplier_bps(staked_seconds: i64) -> u64 {
+    let bonus = (staked_seconds.max(0) as u128) * HERITAGE_BONUS_BPS_PER_MONTH as u128 / HERITAGE_MONTH as u128;
+    std::cmp::min(BPS_DENOMINATOR as u128 + bonus, MAX_HERITAGE_MULTIPLIER_BPS as u128) as u64
+}
+
+/// Governance power of a position: `amount * multiplier^2`, as in `calculateVotingPower`.
+pub fn voting_power(amount: u64, staked_seconds: i64) -> Result<u64> {
+    let multiplier = heritage_multiplier_bps(staked_seconds) as u128;
+    let denominator = (BPS_DENOMINATOR as u128) * (BPS_DENOMINATOR as u128);
+    let power = (amount as u128) * multiplier * multiplier / denominator;
+    Ok(u64::try_from(power).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// Replaces `old` with `new` inside an aggregate.
+fn shift(total: u64, old: u64, new: u64) -> Result<u64> {
+    Ok(total.checked_sub(old).and_then(|t| t.checked_add(new)).ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Recomputes a position's voting power and credits the change to whoever holds its
+/// votes (the owner, or their delegatee), checkpointing every affected history.
+pub fn sync_voting_power(
+    user_stake: &mut UserStake,
+    clock: &Clock,
+    user_history: &mut StakeHistory,
+    pool_history: &mut StakeHistory,
+    delegation: Option<&mut Delegation>,
+    delegatee_history: Option<&mut StakeHistory>,
+) -> Result<()> {
+    let old_power = user_stake.voting_power;
+    let new_power = voting_power(user_stake.amount, clock.unix_timestamp.saturating_sub(user_stake.start_time))?;
+
+    if user_stake.delegatee == Pubkey::default() {
+        user_history.power = shift(user_history.power, old_power, new_power)?;
+    } else {
+        let (delegation, delegatee_history) =
+            delegation.zip(delegatee_history).ok_or(ErrorCode::DelegationRequired)?;
+        require!(
+            delegation.delegator == user_stake.owner
+                && delegation.pool == user_stake.pool
+                && delegation.delegatee == user_stake.delegatee,
+            ErrorCode::InvalidDelegation
+        );
+        require!(
+            delegatee_history.owner == user_stake.delegatee && delegatee_history.pool == user_stake.pool,
+            ErrorCode::InvalidDelegation
+        );
+        delegation.power = new_power;
+        delegatee_history.delegated_power = shift(delegatee_history.delegated_power, old_power, new_power)?;
+        delegatee_history.checkpoint(clock.slot)?;
+    }
+    user_history.checkpoint(clock.slot)?;
+    pool_history.power = shift(pool_history.power, old_power, new_power)?;
+    pool_history.checkpoint(clock.slot)?;
+
+    user_stake.voting_power = new_power;
+    Ok(())
+}
+
+/// Reward per unit of weight when `emitted` is split across `total_weight`, scaled by `REWARD_PRECISION`.
+pub fn reward_per_weight(emitted: u64, total_weight: u64) -> Result<u128> {
+    if total_weight == 0 {
+        return Ok(0);
+    }
+    Ok((emitted as u128)
+        .checked_mul(REWARD_PRECISION)
+        .ok_or(ErrorCode::MathOverflow)?
+        / total_weight as u128)
+}
+
+/// Reward earned by `weight` while the accumulator moved from `paid` to `acc_reward_per_weight`.
+pub fn accrued_reward(weight: u64, acc_reward_per_weight: u128, paid: u128) -> Result<u64> {
+    let earned = (weight as u128)
+        .checked_mul(acc_reward_per_weight.saturating_sub(paid))
+        .ok_or(ErrorCode::MathOverflow)?
+        / REWARD_PRECISION;
+    u64::try_from(earned).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Stake weight after applying a boost, with the boost capped at `max_boost_bps`.
+pub fn boosted_weight(amount: u64, boost_bps: u64, max_boost_bps: u64) -> Result<u64> {
+    let multiplier = BPS_DENOMINATOR + std::cmp::min(boost_bps, max_boost_bps);
+    let weight = (amount as u128)
+        .checked_mul(multiplier as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(weight).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// True if `proof` shows `owner` still holds `nft_mint`, either in a token account
+/// or escrowed in this program's NFT staking.
+pub fn booster_held(proof: &AccountInfo, owner: &Pubkey, nft_mint: &Pubkey) -> bool {
+    let data = match proof.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    if proof.owner == &token::ID {
+        match TokenAccount::try_deserialize(&mut &data[..]) {
+            Ok(account) => account.mint == *nft_mint && account.owner == *owner && account.amount == 1,
+            Err(_) => false,
+        }
+    } else if proof.owner == &crate::ID {
+        match NftStake::try_deserialize(&mut &data[..]) {
+            Ok(stake) => stake.nft_mint == *nft_mint && stake.owner == *owner,
+            Err(_) => false,
+        }
+    } else {
+        false
+    }
+}
+
+/// Flags which registered boosters `proofs` show the owner still holds. `proofs` holds
+/// one account per registered booster, in order.
+pub fn held_boosters(user_stake: &UserStake, proofs: &[AccountInfo]) -> [bool; MAX_BOOSTERS] {
+    let mut held = [false; MAX_BOOSTERS];
+    for (i, booster) in user_stake.active_boosters().iter().enumerate() {
+        held[i] = proofs
+            .get(i)
+            .map(|proof| booster_held(proof, &user_stake.owner, &booster.nft_mint))
+            .unwrap_or(false);
+    }
+    held
+}
+
+/// Settles a position after re-checking its boosters. Boosters that are no longer held
+/// earn nothing for the period being settled and are removed.
+pub fn settle_position(pool: &mut StakePool, user_stake: &mut UserStake, proofs: &[AccountInfo]) -> Result<()> {
+    let held = held_boosters(user_stake, proofs);
+    if held[..user_stake.booster_count as usize].iter().all(|h| *h) {
+        return user_stake.settle(pool.acc_reward_per_weight);
+    }
+
+    user_stake.retain_boosters(&held);
+    let weight = boosted_weight(user_stake.amount, user_stake.boost_bps(pool), pool.max_boost_bps as u64)?;
+    user_stake.settle_at(pool.acc_reward_per_weight, weight)?;
+    user_stake.reweight(pool)
+}
+
+/// Splits a compounded reward into the part restaked and the caller's bounty.
+pub fn split_bounty(reward: u64, bounty_bps: u16) -> Result<(u64, u64)> {
+    let bounty = (reward as u128)
+        .checked_mul(bounty_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    let bounty = std::cmp::min(bounty as u64, reward);
+    Ok((reward - bounty, bounty))
+}
+
+/// Splits the early-exit penalty on `amount` into `(burned, to_treasury)`. The penalty
+/// starts at `max_penalty_bps` and decays linearly with the remaining lock time.
+pub fn early_exit_penalty(
+    amount: u64,
+    remaining: i64,
+    lock_duration: i64,
+    max_penalty_bps: u16,
+    burn_bps: u16,
+) -> Result<(u64, u64)> {
+    if remaining <= 0 || lock_duration <= 0 {
+        return Ok((0, 0));
+    }
+    let remaining = std::cmp::min(remaining, lock_duration) as u128;
+    let penalty = (amount as u128)
+        .checked_mul(max_penalty_bps as u128)
+        .and_then(|v| v.checked_mul(remaining))
+        .ok_or(ErrorCode::MathOverflow)?
+        / (BPS_DENOMINATOR as u128 * lock_duration as u128);
+    let burned = penalty * burn_bps as u128 / BPS_DENOMINATOR as u128;
+    Ok((burned as u64, (penalty - burned) as u64))
+}
+
+/// Receipt tokens minted for a liquid deposit at the current exchange rate.
+pub fn shares_for_deposit(amount: u64, liquid_staked: u64, receipt_supply: u64) -> Result<u64> {
+    if receipt_supply == 0 || liquid_staked == 0 {
+        return Ok(amount);
+    }
+    let shares = (amount as u128)
+        .checked_mul(receipt_supply as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / liquid_staked as u128;
+    u64::try_from(shares).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Underlying tokens owed for `shares` receipt tokens at the current exchange rate.
+pub fn underlying_for_shares(shares: u64, liquid_staked: u64, receipt_supply: u64) -> Result<u64> {
+    require!(shares <= receipt_supply, ErrorCode::InsufficientFunds);
+    let underlying = (shares as u128)
+        .checked_mul(liquid_staked as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / receipt_supply as u128;
+    u64::try_from(underlying).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// GRIT accrued by a staked NFT since its last claim.
+pub fn nft_reward(rate: u64, last_claim_time: i64, now: i64) -> Result<u64> {
+    let elapsed = now.saturating_sub(last_claim_time).max(0) as u64;
+    rate.checked_mul(elapsed).ok_or_else(|| error!(ErrorCode::MathOverflow))
+}
+
+/// True when the metadata carries a verified collection matching `collection`.
+pub fn is_verified_member(metadata: &MetadataAccount, collection: &Pubkey) -> bool {
+    matches!(&metadata.collection, Some(c) if c.verified && c.key == *collection)
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(init, payer = authority, space = StakingConfig::LEN, seeds = [b"config"], bump)]
+    pub config: Account<'info, StakingConfig>,
+    pub grit_mint: Account<'info, Mint>,
+    pub moxy_mint: Account<'info, Mint>,
+    pub chi_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLegacyVault<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, StakingConfig>,
+    pub legacy_vault: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, StakingConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = StakePool::LEN,
+        seeds = [b"pool", stake_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        init,
+        payer = authority,
+        space = StakeHistory::LEN,
+        seeds = [b"history", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    pub stake_mint: Account<'info, Mint>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = stake_mint,
+        token::authority = pool,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = pool,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetPoolParams<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, StakePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, address = pool.reward_vault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserStake::LEN,
+        seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = StakeHistory::LEN,
+        seeds = [b"history", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(mut, token::mint = pool.stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user.key().as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(mut, token::mint = pool.stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EarlyExit<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump, has_one = stake_mint)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user.key().as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(mut)]
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.penalty_treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = AscesisRecord::LEN,
+        seeds = [b"ascesis", user.key().as_ref(), stake_mint.key().as_ref()],
+        bump
+    )]
+    pub ascesis_record: Account<'info, AscesisRecord>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPenaltyConfig<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, StakePool>,
+    /// The treasury Lung's token account for the staked mint.
+    #[account(token::mint = pool.stake_mint)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, StakePool>,
+    #[account(address = pool.guardian @ ErrorCode::Unauthorized)]
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSlashConfig<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, StakePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Slash<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump, has_one = stake_mint)]
+    pub pool: Account<'info, StakePool>,
+    #[account(address = pool.slash_authority @ ErrorCode::Unauthorized)]
+    pub slash_authority: Signer<'info>,
+    /// CHECK: The owner of the slashed position; only used to derive its accounts.
+    pub user: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user.key().as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(
+        init,
+        payer = payer,
+        space = SlashRecord::LEN,
+        seeds = [b"slash", pool.key().as_ref(), pool.slash_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub slash_record: Account<'info, SlashRecord>,
+    #[account(mut)]
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.penalty_treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, address = pool.reward_vault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.reward_mint)]
+    pub user_reward_account: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetCompoundConfig<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, StakePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoCompound<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Compound<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user_stake.owner.as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.reward_vault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.reward_mint)]
+    pub caller_reward_account: Account<'info, TokenAccount>,
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetBoostConfig<'info> {
+    #[account(mut, has_one = authority)]
+    pub pool: Account<'info, StakePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterBooster<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.amount > 0 @ ErrorCode::StakeInactive
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(seeds = [b"rarity_table", pool.boost_collection.as_ref()], bump = rarity_table.bump)]
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(
+        seeds = [b"rarity", rarity_table.key().as_ref(), nft_mint.key().as_ref()],
+        bump = rarity_entry.bump
+    )]
+    pub rarity_entry: Account<'info, RarityEntry>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = Metadata::id(),
+        bump
+    )]
+    pub nft_metadata: Account<'info, MetadataAccount>,
+    /// CHECK: Token account or `NftStake` holding the NFT; validated by `booster_held`.
+    pub booster_proof: UncheckedAccount<'info>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveBooster<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableLiquidStaking<'info> {
+    #[account(mut, has_one = authority, has_one = stake_mint)]
+    pub pool: Account<'info, StakePool>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = stake_mint.decimals,
+        mint::authority = pool,
+        seeds = [b"receipt", pool.key().as_ref()],
+        bump
+    )]
+    pub receipt_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct StakeLiquid<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, address = pool.receipt_mint @ ErrorCode::LiquidNotEnabled)]
+    pub receipt_mint: Account<'info, Mint>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.reward_vault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.receipt_mint)]
+    pub user_receipt_account: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, address = pool.receipt_mint @ ErrorCode::LiquidNotEnabled)]
+    pub receipt_mint: Account<'info, Mint>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, address = pool.reward_vault)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.receipt_mint)]
+    pub user_receipt_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = RedeemTicket::LEN,
+        seeds = [b"redeem", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub redeem_ticket: Account<'info, RedeemTicket>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRedeemed<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"redeem", pool.key().as_ref(), user.key().as_ref()],
+        bump = redeem_ticket.bump
+    )]
+    pub redeem_ticket: Account<'info, RedeemTicket>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = pool.stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRarityTable<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, StakingConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = RarityTable::LEN,
+        seeds = [b"rarity_table", collection_mint.key().as_ref()],
+        bump
+    )]
+    pub rarity_table: Account<'info, RarityTable>,
+    pub collection_mint: Account<'info, Mint>,
+    #[account(address = config.grit_mint @ ErrorCode::InvalidMint)]
+    pub grit_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = grit_mint,
+        token::authority = rarity_table,
+        seeds = [b"nft_reward_vault", rarity_table.key().as_ref()],
+        bump
+    )]
+    pub nft_reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetRarityRates<'info> {
+    #[account(mut, has_one = authority)]
+    pub rarity_table: Account<'info, RarityTable>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftRarity<'info> {
+    #[account(has_one = authority)]
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RarityEntry::LEN,
+        seeds = [b"rarity", rarity_table.key().as_ref(), nft_mint.key().as_ref()],
+        bump
+    )]
+    pub rarity_entry: Account<'info, RarityEntry>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundNftRewards<'info> {
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(mut, address = rarity_table.reward_vault)]
+    pub nft_reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeNft<'info> {
+    #[account(seeds = [b"rarity_table", rarity_table.collection.as_ref()], bump = rarity_table.bump)]
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(
+        seeds = [b"rarity", rarity_table.key().as_ref(), nft_mint.key().as_ref()],
+        bump = rarity_entry.bump
+    )]
+    pub rarity_entry: Account<'info, RarityEntry>,
+    #[account(
+        init,
+        payer = user,
+        space = NftStake::LEN,
+        seeds = [b"nft_stake", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub nft_stake: Account<'info, NftStake>,
+    #[account(constraint = nft_mint.decimals == 0 && nft_mint.supply == 1 @ ErrorCode::InvalidCollection)]
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"metadata", Metadata::id().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = Metadata::id(),
+        bump
+    )]
+    pub nft_metadata: Account<'info, MetadataAccount>,
+    #[account(mut, token::mint = nft_mint, token::authority = user)]
+    pub user_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = user,
+        token::mint = nft_mint,
+        token::authority = nft_stake,
+        seeds = [b"nft_escrow", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimNftReward<'info> {
+    #[account(seeds = [b"rarity_table", rarity_table.collection.as_ref()], bump = rarity_table.bump)]
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(
+        mut,
+        seeds = [b"nft_stake", nft_stake.nft_mint.as_ref()],
+        bump = nft_stake.bump,
+        has_one = rarity_table,
+        constraint = nft_stake.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub nft_stake: Account<'info, NftStake>,
+    #[account(mut, address = rarity_table.reward_vault)]
+    pub nft_reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = nft_reward_vault.mint)]
+    pub user_reward_account: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeNft<'info> {
+    #[account(seeds = [b"rarity_table", rarity_table.collection.as_ref()], bump = rarity_table.bump)]
+    pub rarity_table: Account<'info, RarityTable>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"nft_stake", nft_stake.nft_mint.as_ref()],
+        bump = nft_stake.bump,
+        has_one = rarity_table,
+        constraint = nft_stake.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub nft_stake: Account<'info, NftStake>,
+    #[account(mut, seeds = [b"nft_escrow", nft_stake.nft_mint.as_ref()], bump)]
+    pub nft_escrow: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = nft_stake.nft_mint)]
+    pub user_nft_account: Account<'info, TokenAccount>,
+    #[account(mut, address = rarity_table.reward_vault)]
+    pub nft_reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = nft_reward_vault.mint)]
+    pub user_reward_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStake<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, StakingConfig>,
+    #[account(mut, seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    /// CHECK: Holds a v0 `UserStake`, which does not deserialize as the current
+    /// layout; its discriminator, length and owner are checked in the handler.
+    #[account(mut, owner = crate::ID, seeds = [b"stake", owner.key().as_ref()], bump)]
+    pub legacy_stake: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserStake::LEN,
+        seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StakeHistory::LEN,
+        seeds = [b"history", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    /// The v0 stake vault holding the position's principal.
+    #[account(
+        mut,
+        address = config.legacy_vault @ ErrorCode::InvalidLegacyVault,
+        token::mint = pool.stake_mint,
+        token::authority = legacy_vault_authority
+    )]
+    pub legacy_vault: Account<'info, TokenAccount>,
+    pub legacy_vault_authority: Signer<'info>,
+    #[account(mut, address = pool.vault)]
+    pub vault: Account<'info, TokenAccount>,
+    /// The position's owner; derives both positions and receives the v0 rent.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone: u8)]
+pub struct MintHeritageBadge<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(seeds = [b"stake", pool.key().as_ref(), owner.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: The staker receiving the badge; only used to derive accounts.
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: Created and initialized as a Token-2022 mint in the handler; the
+    /// seeds make each (position, milestone) badge mintable once.
+    #[account(
+        mut,
+        seeds = [b"heritage_badge", user_stake.key().as_ref(), &[milestone]],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+    /// CHECK: The owner's associated token account for `badge_mint`, created by the
+    /// associated token program, which verifies the address.
+    #[account(mut)]
+    pub badge_token_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshVotingPower<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake", pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user_stake.owner.as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub delegation: Option<Box<Account<'info, Delegation>>>,
+    #[account(mut)]
+    pub delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+}
+
+#[derive(Accounts)]
+pub struct WeightAt<'info> {
+    pub history: Account<'info, StakeHistory>,
+}
+
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Delegation::LEN,
+        seeds = [b"delegation", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    /// Co-signs, so no wallet can write checkpoints into another's history uninvited.
+    pub delegatee: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = StakeHistory::LEN,
+        seeds = [b"history", pool.key().as_ref(), delegatee.key().as_ref()],
+        bump
+    )]
+    pub delegatee_history: Box<Account<'info, StakeHistory>>,
+    /// History of the current delegatee when changing an existing delegation.
+    #[account(mut)]
+    pub previous_delegatee_history: Option<Box<Account<'info, StakeHistory>>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user.key().as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    #[account(seeds = [b"pool", pool.stake_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, seeds = [b"stake", pool.key().as_ref(), user.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"delegation", pool.key().as_ref(), user.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    #[account(
+        mut,
+        seeds = [b"history", pool.key().as_ref(), delegation.delegatee.as_ref()],
+        bump = delegatee_history.bump
+    )]
+    pub delegatee_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref(), user.key().as_ref()], bump = user_history.bump)]
+    pub user_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut, seeds = [b"history", pool.key().as_ref()], bump = pool_history.bump)]
+    pub pool_history: Box<Account<'info, StakeHistory>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BurnForAscesis<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = AscesisRecord::LEN,
+        seeds = [b"ascesis", user.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub ascesis_record: Account<'info, AscesisRecord>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(ring: u8)]
+pub struct ConfigureRing<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, StakingConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AscesisRing::LEN,
+        seeds = [b"ring", ring.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ascesis_ring: Account<'info, AscesisRing>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(ring: u8, token_type: TokenType)]
+pub struct AdvanceRing<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, StakingConfig>,
+    #[account(seeds = [b"ring", ring.to_le_bytes().as_ref()], bump = ascesis_ring.bump)]
+    pub ascesis_ring: Account<'info, AscesisRing>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = RingProgress::LEN,
+        seeds = [b"ring_progress", user.key().as_ref()],
+        bump
+    )]
+    pub ring_progress: Account<'info, RingProgress>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = AscesisRecord::LEN,
+        seeds = [b"ascesis", user.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub ascesis_record: Account<'info, AscesisRecord>,
+    #[account(mut, address = config.mint_for(token_type) @ ErrorCode::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct StakingConfig {
+    pub authority: Pubkey,
+    pub grit_mint: Pubkey,
+    pub moxy_mint: Pubkey,
+    pub chi_mint: Pubkey,
+    pub legacy_vault: Pubkey, // v0 stake vault drained by `migrate_stake`
+    pub bump: u8,
+}
+
+impl StakingConfig {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 1;
+
+    pub fn mint_for(&self, token_type: TokenType) -> Pubkey {
+        match token_type {
+            TokenType::Grit => self.grit_mint,
+            TokenType::Moxy => self.moxy_mint,
+            TokenType::Chi => self.chi_mint,
+        }
+    }
+}
+
+/// The ritual tokens a ring can be paid in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenType {
+    Grit,
+    Moxy,
+    Chi,
+}
+
+impl TokenType {
+    pub fn index(self) -> usize {
+        match self {
+            TokenType::Grit => 0,
+            TokenType::Moxy => 1,
+            TokenType::Chi => 2,
+        }
+    }
+}
+
+/// An Ascesis ring. `required_burns` is indexed by `TokenType`; zero means
+/// the ring cannot be entered with that token.
+#[account]
+pub struct AscesisRing {
+    pub index: u8,
+    pub required_burns: [u64; 3],
+    pub min_burn_count: u64, // Prior burns of the paying mint
+    pub bump: u8,
+}
+
+impl AscesisRing {
+    pub const LEN: usize = 8 + 1 + 8 * 3 + 8 + 1;
+
+    /// Burn needed to climb from `ring_level` to `ring` paying with `token_type`, given
+    /// `burn_count` prior burns of that mint. Rings are climbed one at a time.
+    pub fn burn_required(&self, ring_level: u8, ring: u8, burn_count: u64, token_type: TokenType) -> Result<u64> {
+        require!(ring_level.checked_add(1) == Some(ring), ErrorCode::RingOutOfOrder);
+        require!(burn_count >= self.min_burn_count, ErrorCode::RingPrerequisiteNotMet);
+        let amount = self.required_burns[token_type.index()];
+        require!(amount > 0, ErrorCode::TokenNotAccepted);
+        Ok(amount)
+    }
+}
+
+#[account]
+pub struct RingProgress {
+    pub owner: Pubkey,
+    pub ring_level: u8,
+    pub last_advance_time: i64,
+    pub bump: u8,
+}
+
+impl RingProgress {
+    pub const LEN: usize = 8 + 32 + 1 + 8 + 1;
+}
+
+#[event]
+pub struct RingAdvanced {
+    pub user: Pubkey,
+    pub ring: u8,
+    pub token_type: TokenType,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolPaused {
+    pub pool: Pubkey,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUnpaused {
+    pub pool: Pubkey,
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// A staking pool for one mint. Rewards are streamed by `schedule`, capped by what
+/// has been funded, and split across stakers by weight using `acc_reward_per_weight`.
+#[account]
+#[derive(Default)]
+pub struct StakePool {
+    pub authority: Pubkey,
+    pub stake_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub vault: Pubkey,
+    pub reward_vault: Pubkey,
+    pub schedule: EmissionSchedule,
+    pub lockup_duration: i64,  // Seconds a position is locked after each stake
+    pub total_staked: u64,
+    pub total_weight: u64,
+    pub acc_reward_per_weight: u128, // Scaled by REWARD_PRECISION
+    pub last_update_time: i64,
+    pub bump: u8,
+    pub receipt_mint: Pubkey,       // Default until liquid staking is enabled
+    pub liquid_staked: u64,         // Underlying backing the receipt supply
+    pub liquid_reward_paid: u128,
+    pub liquid_reward_pending: u64, // Earned by the liquid share but not yet compounded
+    pub boost_collection: Pubkey,                  // Default disables boosters
+    pub boost_bps: [u16; RARITY_TIERS],            // Added weight per booster, by `RarityTier`
+    pub max_boost_bps: u16,                        // Cap on the summed boost of one position
+    pub compound_interval: i64,                    // Minimum seconds between `compound` cranks
+    pub compound_bounty_bps: u16,                  // Share of compounded rewards paid to the caller
+    pub total_funded: u64,                         // Deposited through `fund_rewards`
+    pub total_emitted: u64,                        // Promised to stakers so far
+    pub max_exit_penalty_bps: u16,                 // Early-exit penalty at the start of a lock
+    pub penalty_burn_bps: u16,                     // Share of the penalty burned; the rest goes to the treasury
+    pub penalty_treasury: Pubkey,
+    pub slash_authority: Pubkey,                   // Default disables slashing
+    pub max_slash_bps_per_epoch: u16,              // Share of the epoch's opening stake that may be slashed
+    pub slash_burn_bps: u16,                       // Share of a slash burned; the rest goes to the treasury
+    pub slash_epoch: u64,
+    pub slash_epoch_base: u64,                     // `total_staked` when `slash_epoch` began
+    pub slashed_in_epoch: u64,
+    pub slash_count: u64,                          // Seeds the next `SlashRecord`
+    pub guardian: Pubkey,                          // May pause the pool; default disables pausing
+    pub paused: bool,
+}
+
+impl StakePool {
+    pub const LEN: usize = 8 + 32 * 5 + EmissionSchedule::LEN + 8 + 8 + 8 + 16 + 8 + 1 + 32 + 8 + 16 + 8 + 32 + 2 * RARITY_TIERS + 2 + 8 + 2 + 8 + 8 + 2 + 2 + 32 + 32 + 2 + 2 + 8 + 8 + 8 + 8 + 32 + 1;
+
+    /// Seeds for signing as the pool PDA.
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [b"pool", self.stake_mint.as_ref(), std::slice::from_ref(&self.bump)]
+    }
+
+    /// Accrues rewards up to `now`. Time with no stakers emits nothing, and emissions
+    /// stop once the schedule's budget or the funded amount is used up.
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        if now <= self.last_update_time {
+            return Ok(());
+        }
+        if self.total_weight > 0 {
+            let cap = std::cmp::min(self.schedule.budget, self.total_funded);
+            let remaining = cap.saturating_sub(self.total_emitted);
+            let emitted = std::cmp::min(self.schedule.emitted_between(self.last_update_time, now)?, remaining);
+            self.acc_reward_per_weight = self
+                .acc_reward_per_weight
+                .checked_add(reward_per_weight(emitted, self.total_weight)?)
+                .ok_or(ErrorCode::MathOverflow)?;
+            self.total_emitted = self.total_emitted.checked_add(emitted).ok_or(ErrorCode::MathOverflow)?;
+        }
+        self.last_update_time = now;
+        Ok(())
+    }
+
+    /// Restakes the rewards earned by the liquid share of the pool, limited to what
+    /// the reward vault holds; any shortfall stays pending for a later call. Returns
+    /// the amount to move from the reward vault into the stake vault. Call `update` first.
+    pub fn compound_liquid(&mut self, reward_vault_balance: u64) -> Result<u64> {
+        self.settle_liquid()?;
+        let compounded = std::cmp::min(self.liquid_reward_pending, reward_vault_balance);
+        self.liquid_reward_pending -= compounded;
+        self.add_liquid(compounded)?;
+        Ok(compounded)
+    }
+
+    /// Moves the liquid share's accrued rewards into `liquid_reward_pending`, so the
+    /// liquid balance can change without losing them.
+    fn settle_liquid(&mut self) -> Result<()> {
+        let earned = accrued_reward(self.liquid_staked, self.acc_reward_per_weight, self.liquid_reward_paid)?;
+        self.liquid_reward_pending = self.liquid_reward_pending.checked_add(earned).ok_or(ErrorCode::MathOverflow)?;
+        self.liquid_reward_paid = self.acc_reward_per_weight;
+        Ok(())
+    }
+
+    pub fn add_liquid(&mut self, amount: u64) -> Result<()> {
+        self.settle_liquid()?;
+        self.liquid_staked = self.liquid_staked.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.total_staked = self.total_staked.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.total_weight = self.total_weight.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Counts `amount` against the slashing limit of `epoch`. The limit is a share of
+    /// the stake at the epoch's first slash, so repeated slashes cannot ratchet it down
+    /// and a compromised authority can only take a bounded amount per epoch.
+    pub fn charge_slash(&mut self, epoch: u64, amount: u64) -> Result<()> {
+        if epoch != self.slash_epoch {
+            self.slash_epoch = epoch;
+            self.slash_epoch_base = self.total_staked;
+            self.slashed_in_epoch = 0;
+        }
+        let limit = (self.slash_epoch_base as u128) * (self.max_slash_bps_per_epoch as u128) / BPS_DENOMINATOR as u128;
+        let slashed = self.slashed_in_epoch.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        require!(slashed as u128 <= limit, ErrorCode::SlashLimitExceeded);
+        self.slashed_in_epoch = slashed;
+        Ok(())
+    }
+
+    pub fn remove_liquid(&mut self, amount: u64) -> Result<()> {
+        self.settle_liquid()?;
+        self.liquid_staked = self.liquid_staked.checked_sub(amount).ok_or(ErrorCode::InsufficientFunds)?;
+        self.total_staked = self.total_staked.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.total_weight = self.total_weight.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Emission curve for a pool: `initial_rate` per second from `start_time`, halved
+/// every `halving_interval` seconds, never exceeding `budget` in total.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct EmissionSchedule {
+    pub start_time: i64,
+    pub initial_rate: u64,
+    pub halving_interval: i64, // Zero keeps the rate flat
+    pub budget: u64,
+}
+
+impl EmissionSchedule {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    pub fn rate_at(&self, t: i64) -> u64 {
+        if t < self.start_time {
+            return 0;
+        }
+        if self.halving_interval == 0 {
+            return self.initial_rate;
+        }
+        let halvings = (t - self.start_time) / self.halving_interval;
+        if halvings >= 64 {
+            0
+        } else {
+            self.initial_rate >> halvings
+        }
+    }
+
+    /// Rewards emitted over `[from, to)`, integrated piecewise across halvings.
+    pub fn emitted_between(&self, from: i64, to: i64) -> Result<u64> {
+        let mut t = std::cmp::max(from, self.start_time);
+        let mut total: u128 = 0;
+        while t < to {
+            let rate = self.rate_at(t);
+            if rate == 0 {
+                break;
+            }
+            let segment_end = if self.halving_interval == 0 {
+                to
+            } else {
+                let halvings = (t - self.start_time) / self.halving_interval;
+                let boundary = self
+                    .start_time
+                    .saturating_add((halvings + 1).saturating_mul(self.halving_interval));
+                std::cmp::min(to, boundary)
+            };
+            total = total
+                .checked_add(rate as u128 * (segment_end - t) as u128)
+                .ok_or(ErrorCode::MathOverflow)?;
+            t = segment_end;
+        }
+        Ok(u64::try_from(total).unwrap_or(u64::MAX))
+    }
+}
+
+/// Underlying owed to a liquid staker after `redeem`, claimable once `release_time` passes.
+#[account]
+pub struct RedeemTicket {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub release_time: i64,
+    pub bump: u8,
+}
+
+impl RedeemTicket {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// `UserStake` as deployed before staking pools: 56 bytes at `[b"stake", owner]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UserStakeV0 {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub start_time: i64,
+}
+
+impl UserStakeV0 {
+    pub const LEN: usize = 8 + 32 + 8 + 8;
+}
+
+/// A staker's position in one pool. `version` leads the layout so future migrations
+/// can tell formats apart; `reserved` leaves room for new fields without a realloc.
+#[account]
+#[derive(Default)]
+pub struct UserStake {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub start_time: i64,
+    pub pool: Pubkey,
+    pub weight: u64,
+    pub reward_paid: u128, // Pool accumulator at the last settlement
+    pub pending_rewards: u64,
+    pub lock_end: i64,
+    pub bump: u8,
+    pub booster_count: u8,
+    pub boosters: [Booster; MAX_BOOSTERS],
+    pub auto_compound: bool,
+    pub last_compound_time: i64,
+    pub lock_duration: i64, // Length of the lock ending at `lock_end`
+    pub delegatee: Pubkey,  // Default key when the owner votes for themselves
+    pub voting_power: u64,  // Power last credited to the owner or delegatee
+    pub reserved: [u8; 32],
+}
+
+impl UserStake {
+    pub const LEN: usize = 8 + 1 + 32 + 8 + 8 + 32 + 8 + 16 + 8 + 8 + 1 + 1 + Booster::LEN * MAX_BOOSTERS + 1 + 8 + 8 + 32 + 8 + 32;
+
+    /// Moves rewards earned since the last settlement into `pending_rewards`.
+    pub fn settle(&mut self, acc_reward_per_weight: u128) -> Result<()> {
+        self.settle_at(acc_reward_per_weight, self.weight)
+    }
+
+    /// Like `settle`, but credits the period at `weight` instead of the stored weight.
+    pub fn settle_at(&mut self, acc_reward_per_weight: u128, weight: u64) -> Result<()> {
+        let earned = accrued_reward(weight, acc_reward_per_weight, self.reward_paid)?;
+        self.pending_rewards = self.pending_rewards.checked_add(earned).ok_or(ErrorCode::MathOverflow)?;
+        self.reward_paid = acc_reward_per_weight;
+        Ok(())
+    }
+
+    /// Removes the whole principal and its weight from the pool without settling,
+    /// returning the amount to pay out.
+    pub fn release_all(&mut self, pool: &mut StakePool) -> Result<u64> {
+        let amount = self.amount;
+        pool.total_staked = pool.total_staked.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_weight = pool.total_weight.checked_sub(self.weight).ok_or(ErrorCode::MathOverflow)?;
+        self.amount = 0;
+        self.weight = 0;
+        self.reward_paid = pool.acc_reward_per_weight;
+        Ok(amount)
+    }
+
+    /// Recomputes this position's weight and updates the pool total. Call `settle` first.
+    pub fn reweight(&mut self, pool: &mut StakePool) -> Result<()> {
+        let new_weight = boosted_weight(self.amount, self.boost_bps(pool), pool.max_boost_bps as u64)?;
+        pool.total_weight = pool
+            .total_weight
+            .checked_sub(self.weight)
+            .and_then(|w| w.checked_add(new_weight))
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.weight = new_weight;
+        Ok(())
+    }
+
+    /// Adds a v0 position's principal, keeping the earlier of the two start times.
+    /// Settle the position first.
+    pub fn absorb_v0(&mut self, legacy: &UserStakeV0, pool: &mut StakePool) -> Result<()> {
+        self.start_time = if self.amount == 0 { legacy.start_time } else { self.start_time.min(legacy.start_time) };
+        self.amount = self.amount.checked_add(legacy.amount).ok_or(ErrorCode::MathOverflow)?;
+        pool.total_staked = pool.total_staked.checked_add(legacy.amount).ok_or(ErrorCode::MathOverflow)?;
+        self.reweight(pool)
+    }
+
+    pub fn active_boosters(&self) -> &[Booster] {
+        &self.boosters[..self.booster_count as usize]
+    }
+
+    /// Summed boost of the registered boosters at the pool's current rates, uncapped.
+    pub fn boost_bps(&self, pool: &StakePool) -> u64 {
+        self.active_boosters()
+            .iter()
+            .map(|b| pool.boost_bps[b.tier.index()] as u64)
+            .sum()
+    }
+
+    pub fn add_booster(&mut self, booster: Booster) -> Result<()> {
+        require!(
+            !self.active_boosters().iter().any(|b| b.nft_mint == booster.nft_mint),
+            ErrorCode::BoosterAlreadyRegistered
+        );
+        require!((self.booster_count as usize) < MAX_BOOSTERS, ErrorCode::TooManyBoosters);
+        self.boosters[self.booster_count as usize] = booster;
+        self.booster_count += 1;
+        Ok(())
+    }
+
+    /// Keeps only the boosters flagged in `keep`, preserving their order.
+    pub fn retain_boosters(&mut self, keep: &[bool; MAX_BOOSTERS]) {
+        let mut kept = [Booster::default(); MAX_BOOSTERS];
+        let mut count = 0;
+        for (i, booster) in self.active_boosters().iter().enumerate() {
+            if keep[i] {
+                kept[count] = *booster;
+                count += 1;
+            }
+        }
+        self.boosters = kept;
+        self.booster_count = count as u8;
+    }
+}
+
+/// A collection NFT registered to boost a position.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Booster {
+    pub nft_mint: Pubkey,
+    pub tier: RarityTier,
+}
+
+impl Booster {
+    pub const LEN: usize = 32 + 1;
+}
+
+/// Voting power over time (slot → power) for one wallet, or for the whole pool
+/// when `owner` is the default key. Used to measure stake at a past slot.
+///
+/// Only the owner's own position and delegators the owner accepted write here.
+#[account]
+#[derive(Default)]
+pub struct StakeHistory {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub power: u64,           // Power of the owner's own, undelegated position
+    pub delegated_power: u64, // Power delegated to the owner by other stakers
+    pub len: u16,
+    pub checkpoints: [Checkpoint; MAX_CHECKPOINTS],
+    pub bump: u8,
+}
+
+impl StakeHistory {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 2 + Checkpoint::LEN * MAX_CHECKPOINTS + 1;
+
+    pub fn active(&self) -> &[Checkpoint] {
+        &self.checkpoints[..self.len as usize]
+    }
+
+    /// Slots before the latest checkpoint over which lookups stay exact. The pool
+    /// history is only read for the slot before the current one, by `open_voting`.
+    pub fn retention_slots(&self) -> u64 {
+        if self.owner == Pubkey::default() {
+            1
+        } else {
+            HISTORY_RETENTION_SLOTS
+        }
+    }
+
+    /// Appends a checkpoint, overwriting one already taken in the same slot.
+    /// Checkpoints superseded before the retention window are dropped to make room;
+    /// nothing inside the window is ever merged or dropped, so a full window fails
+    /// with `HistoryFull` rather than change a weight a live vote may read.
+    pub fn record(&mut self, slot: u64, weight: u64) -> Result<()> {
+        if let Some(last) = self.active().last() {
+            require!(slot >= last.slot, ErrorCode::InvalidCheckpoint);
+            if last.slot == slot {
+                self.checkpoints[self.len as usize - 1].weight = weight;
+                return Ok(());
+            }
+        }
+        let horizon = slot.saturating_sub(self.retention_slots());
+        let stale = self.active().windows(2).take_while(|pair| pair[1].slot <= horizon).count();
+        if stale > 0 {
+            self.checkpoints.copy_within(stale..self.len as usize, 0);
+            self.len -= stale as u16;
+        }
+        require!((self.len as usize) < MAX_CHECKPOINTS, ErrorCode::HistoryFull);
+        self.checkpoints[self.len as usize] = Checkpoint { slot, weight };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Whether `refresh_voting_power` may checkpoint this history at `slot`; every
+    /// `sync_voting_power` of the owner's position checkpoints it, so this spaces out
+    /// refreshes and keeps a crank from filling the retention window.
+    pub fn refresh_due(&self, slot: u64) -> bool {
+        match self.active().last() {
+            Some(last) => slot >= last.slot.saturating_add(REFRESH_INTERVAL_SLOTS),
+            None => true,
+        }
+    }
+
+    /// Records the current own plus delegated power at `slot`.
+    pub fn checkpoint(&mut self, slot: u64) -> Result<()> {
+        let total = self.power.checked_add(self.delegated_power).ok_or(ErrorCode::MathOverflow)?;
+        self.record(slot, total)
+    }
+
+    /// Weight in effect at `slot`; zero before the first checkpoint.
+    pub fn weight_at(&self, slot: u64) -> u64 {
+        let checkpoints = self.active();
+        match checkpoints.partition_point(|c| c.slot <= slot) {
+            0 => 0,
+            i => checkpoints[i - 1].weight,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub weight: u64,
+}
+
+impl Checkpoint {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// Audit trail for one `slash`; `reason_hash` commits to the off-chain justification.
+#[account]
+#[derive(Default)]
+pub struct SlashRecord {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub burned: u64,
+    pub bps: u16,
+    pub reason_hash: [u8; 32],
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl SlashRecord {
+    pub const LEN: usize = 8 + 32 * 3 + 8 + 8 + 2 + 32 + 8 + 1;
+}
+
+/// A staker's voting power assigned to another wallet.
+#[account]
+#[derive(Default)]
+pub struct Delegation {
+    pub delegator: Pubkey,
+    pub pool: Pubkey,
+    pub delegatee: Pubkey,
+    pub power: u64, // Power currently credited to the delegatee
+    pub bump: u8,
+}
+
+impl Delegation {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Per-tier GRIT emission rates for one NFT collection. Rarity itself lives in
+/// one `RarityEntry` per NFT mint, maintained by the authority.
+#[account]
+#[derive(Default)]
+pub struct RarityTable {
+    pub authority: Pubkey,
+    pub collection: Pubkey,
+    pub reward_vault: Pubkey,
+    pub rates: [u64; RARITY_TIERS], // GRIT base units per second, by `RarityTier`
+    /// Reward earned per NFT of each tier up to `last_accrual_time`.
+    pub accrued: [u64; RARITY_TIERS],
+    pub last_accrual_time: i64,
+    pub bump: u8,
+}
+
+impl RarityTable {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 * RARITY_TIERS + 8 * RARITY_TIERS + 8 + 1;
+
+    pub fn rate_for(&self, tier: RarityTier) -> u64 {
+        self.rates[tier.index()]
+    }
+
+    /// Reward earned per NFT of `tier` from table creation until `now`.
+    pub fn accrued_at(&self, tier: RarityTier, now: i64) -> Result<u64> {
+        let pending = nft_reward(self.rate_for(tier), self.last_accrual_time, now)?;
+        self.accrued[tier.index()].checked_add(pending).ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    /// Closes the current rate period at `now` and starts a new one at `rates`.
+    pub fn set_rates(&mut self, rates: [u64; RARITY_TIERS], now: i64) -> Result<()> {
+        for tier in [RarityTier::Common, RarityTier::Rare, RarityTier::Epic, RarityTier::Legendary] {
+            self.accrued[tier.index()] = self.accrued_at(tier, now)?;
+        }
+        self.last_accrual_time = self.last_accrual_time.max(now);
+        self.rates = rates;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct RarityEntry {
+    pub nft_mint: Pubkey,
+    pub tier: RarityTier,
+    pub bump: u8,
+}
+
+impl RarityEntry {
+    pub const LEN: usize = 8 + 32 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum RarityTier {
+    #[default]
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl RarityTier {
+    pub fn index(self) -> usize {
+        match self {
+            RarityTier::Common => 0,
+            RarityTier::Rare => 1,
+            RarityTier::Epic => 2,
+            RarityTier::Legendary => 3,
+        }
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct NftStake {
+    pub owner: Pubkey,
+    pub nft_mint: Pubkey,
+    pub rarity_table: Pubkey,
+    pub tier: RarityTier,
+    pub start_time: i64,
+    pub reward_debt: u64, // `RarityTable::accrued_at` for `tier` when last settled
+    pub owed: u64,        // settled but unpaid because the reward vault ran short
+    pub bump: u8,
+}
+
+impl NftStake {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 1;
+
+    /// Moves everything earned since the last settlement into `owed`.
+    pub fn settle(&mut self, accrued: u64) -> Result<()> {
+        let earned = accrued.saturating_sub(self.reward_debt);
+        self.owed = self.owed.checked_add(earned).ok_or(ErrorCode::MathOverflow)?;
+        self.reward_debt = self.reward_debt.max(accrued);
+        Ok(())
+    }
+
+    /// Takes as much of `owed` as `available` covers, leaving the rest owed.
+    pub fn take_payout(&mut self, available: u64) -> u64 {
+        let payout = self.owed.min(available);
+        self.owed -= payout;
+        payout
+    }
+}
+
+/// Burn discipline for one user and one mint.
+/// `scars` is a ring buffer: `scar_head` is the next slot to write.
+#[account]
+#[derive(Default)]
+pub struct AscesisRecord {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub initial_burn: u64,
+    pub total_burned: u64,
+    pub burn_count: u64,
+    pub first_burn_time: i64,
+    pub last_burn_time: i64,
+    pub scar_head: u8,
+    pub scars: [Scar; MAX_SCARS],
+    pub bump: u8,
+}
+
+impl AscesisRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + Scar::LEN * MAX_SCARS + 1;
+
+    pub fn record_burn(&mut self, amount: u64, now: i64) -> Result<()> {
+        if self.burn_count == 0 {
+            self.initial_burn = amount;
+            self.first_burn_time = now;
+        }
+        self.total_burned = self.total_burned.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        self.burn_count = self.burn_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+        self.last_burn_time = now;
+
+        let head = self.scar_head as usize % MAX_SCARS;
+        self.scars[head] = Scar { amount, timestamp: now };
+        self.scar_head = ((head + 1) % MAX_SCARS) as u8;
+        Ok(())
+    }
+
+    /// Scars currently held in the ring, oldest first.
+    pub fn recent_scars(&self) -> Vec<Scar> {
+        let held = std::cmp::min(self.burn_count, MAX_SCARS as u64) as usize;
+        let start = (self.scar_head as usize + MAX_SCARS - held) % MAX_SCARS;
+        (0..held).map(|i| self.scars[(start + i) % MAX_SCARS]).collect()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Scar {
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl Scar {
+    pub const LEN: usize = 8 + 8;
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Insufficient staked funds.")]
+    InsufficientFunds,
+    #[msg("Amount must be greater than zero.")]
+    ZeroAmount,
+    #[msg("Arithmetic overflow.")]
+    MathOverflow,
+    #[msg("Ring index must be greater than zero.")]
+    InvalidRing,
+    #[msg("Rings must be advanced one level at a time.")]
+    RingOutOfOrder,
+    #[msg("Not enough prior burns to enter this ring.")]
+    RingPrerequisiteNotMet,
+    #[msg("This ring does not accept the chosen token.")]
+    TokenNotAccepted,
+    #[msg("Mint does not match the configured token.")]
+    InvalidMint,
+    #[msg("Lockup duration cannot be negative.")]
+    InvalidLockup,
+    #[msg("Stake is still locked.")]
+    StakeLocked,
+    #[msg("Reward vault cannot cover the claim.")]
+    InsufficientRewardFunds,
+    #[msg("Liquid staking requires the reward mint to equal the staked mint.")]
+    LiquidRequiresSameMint,
+    #[msg("Liquid staking is already enabled for this pool.")]
+    LiquidAlreadyEnabled,
+    #[msg("Liquid staking is not enabled for this pool.")]
+    LiquidNotEnabled,
+    #[msg("NFT is not a verified member of the collection.")]
+    InvalidCollection,
+    #[msg("Signer does not own this stake.")]
+    Unauthorized,
+    #[msg("Boosters are not enabled for this pool.")]
+    BoostsDisabled,
+    #[msg("Booster NFT is not held by the staker.")]
+    BoosterNotHeld,
+    #[msg("Booster is already registered on this position.")]
+    BoosterAlreadyRegistered,
+    #[msg("Position already has the maximum number of boosters.")]
+    TooManyBoosters,
+    #[msg("Position has no active stake.")]
+    StakeInactive,
+    #[msg("Basis points cannot exceed 10000.")]
+    InvalidBps,
+    #[msg("Compounding requires the reward mint to equal the staked mint.")]
+    CompoundRequiresSameMint,
+    #[msg("Auto-compounding is not enabled for this position.")]
+    AutoCompoundDisabled,
+    #[msg("Compound interval has not elapsed.")]
+    CompoundTooSoon,
+    #[msg("Halving interval cannot be negative.")]
+    InvalidSchedule,
+    #[msg("Stake is not locked; use unstake.")]
+    StakeNotLocked,
+    #[msg("Treasury account does not match the pool configuration.")]
+    InvalidTreasury,
+    #[msg("Legacy vault does not match the staking configuration.")]
+    InvalidLegacyVault,
+    #[msg("Checkpoints must be recorded in slot order.")]
+    InvalidCheckpoint,
+    #[msg("Stake history is full for the retention window; retry later.")]
+    HistoryFull,
+    #[msg("Compound interval is below the minimum.")]
+    CompoundIntervalTooShort,
+    #[msg("Voting power was refreshed too recently.")]
+    RefreshTooSoon,
+    #[msg("Cannot delegate to yourself or to the current delegatee.")]
+    InvalidDelegatee,
+    #[msg("This position is delegated; pass its delegation and the delegatee's history.")]
+    DelegationRequired,
+    #[msg("Delegation accounts do not match this position.")]
+    InvalidDelegation,
+    #[msg("Slash would exceed the pool's per-epoch slashing limit.")]
+    SlashLimitExceeded,
+    #[msg("The pool is paused.")]
+    PoolPaused,
+    #[msg("Emergency withdrawals are only available while the pool is paused.")]
+    PoolNotPaused,
+    #[msg("Account is not a UserStake.")]
+    InvalidStakeAccount,
+    #[msg("Unknown Heritage milestone.")]
+    InvalidMilestone,
+    #[msg("Position has not been staked long enough for this milestone.")]
+    MilestoneNotReached,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stake_at(pool: &mut StakePool, user: &mut UserStake, amount: u64, now: i64) {
+        pool.update(now).unwrap();
+        user.settle(pool.acc_reward_per_weight).unwrap();
+        user.amount += amount;
+        pool.total_staked += amount;
+        user.reweight(pool).unwrap();
+    }
+
+    #[test]
+    fn test_rewards_split_by_weight() {
+        // 100 units/sec. Alice stakes alone for 10s, then Bob joins with 3x her stake.
+        let mut pool = StakePool { schedule: EmissionSchedule { initial_rate: 100, budget: u64::MAX, ..Default::default() }, total_funded: u64::MAX, ..Default::default() };
+        let mut alice = UserStake::default();
+        let mut bob = UserStake::default();
+
+        stake_at(&mut pool, &mut alice, 1_000, 0);
+        stake_at(&mut pool, &mut bob, 3_000, 10);
+
+        pool.update(20).unwrap();
+        alice.settle(pool.acc_reward_per_weight).unwrap();
+        bob.settle(pool.acc_reward_per_weight).unwrap();
+
+        // Alice: 1000 for the first 10s + 250 of the next 1000.
+        assert_eq!(alice.pending_rewards, 1_250);
+        assert_eq!(bob.pending_rewards, 750);
+    }
+
+    #[test]
+    fn test_empty_pool_accrues_nothing() {
+        assert_eq!(reward_per_weight(6_000, 0).unwrap(), 0);
+
+        let mut pool = StakePool { schedule: EmissionSchedule { initial_rate: 100, budget: u64::MAX, ..Default::default() }, total_funded: u64::MAX, ..Default::default() };
+        pool.update(60).unwrap();
+        assert_eq!(pool.acc_reward_per_weight, 0);
+        assert_eq!(pool.last_update_time, 60);
+    }
+
+    #[test]
+    fn test_liquid_exchange_rate_rises_with_compounding() {
+        let mut pool = StakePool { schedule: EmissionSchedule { initial_rate: 10, budget: u64::MAX, ..Default::default() }, total_funded: u64::MAX, ..Default::default() };
+
+        // First deposit mints 1:1.
+        let shares = shares_for_deposit(1_000, pool.liquid_staked, 0).unwrap();
+        assert_eq!(shares, 1_000);
+        pool.add_liquid(1_000).unwrap();
+        let supply = shares;
+
+        // 50s at 10/s, all earned by the liquid share; the vault only holds 400.
+        pool.update(50).unwrap();
+        assert_eq!(pool.compound_liquid(400).unwrap(), 400);
+        assert_eq!(pool.liquid_staked, 1_400);
+
+        // The same receipt supply is now backed by more underlying.
+        assert_eq!(underlying_for_shares(500, pool.liquid_staked, supply).unwrap(), 700);
+        assert_eq!(shares_for_deposit(700, pool.liquid_staked, supply).unwrap(), 500);
+        assert!(underlying_for_shares(supply + 1, pool.liquid_staked, supply).is_err());
+
+        // The 100 the vault could not cover is compounded once it is refilled.
+        assert_eq!(pool.liquid_reward_pending, 100);
+        pool.add_liquid(700).unwrap();
+        assert_eq!(pool.compound_liquid(1_000).unwrap(), 100);
+        assert_eq!((pool.liquid_staked, pool.liquid_reward_pending), (2_200, 0));
+    }
+
+    #[test]
+    fn test_nft_reward_accrues_by_rate() {
+        let table = RarityTable { rates: [1, 5, 20, 100], ..Default::default() };
+        let day = 24 * 60 * 60;
+
+        assert_eq!(nft_reward(table.rate_for(RarityTier::Common), 0, day).unwrap(), 86_400);
+        assert_eq!(nft_reward(table.rate_for(RarityTier::Legendary), 0, day).unwrap(), 8_640_000);
+        // Clock skew never produces a negative reward.
+        assert_eq!(nft_reward(table.rate_for(RarityTier::Epic), 100, 50).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_nft_rate_change_applies_forward() {
+        let mut table = RarityTable { rates: [1, 5, 20, 100], ..Default::default() };
+        let mut stake = NftStake { tier: RarityTier::Rare, ..Default::default() };
+        stake.reward_debt = table.accrued_at(stake.tier, 0).unwrap();
+
+        table.set_rates([1, 50, 20, 100], 100).unwrap();
+        stake.settle(table.accrued_at(stake.tier, 110).unwrap()).unwrap();
+        // 100s at the old rate, then 10s at the new one.
+        assert_eq!(stake.owed, 100 * 5 + 10 * 50);
+
+        // A stake opened after the change only earns the new rate.
+        let mut late = NftStake { tier: RarityTier::Rare, ..Default::default() };
+        late.reward_debt = table.accrued_at(late.tier, 105).unwrap();
+        late.settle(table.accrued_at(late.tier, 110).unwrap()).unwrap();
+        assert_eq!(late.owed, 5 * 50);
+    }
+
+    #[test]
+    fn test_nft_shortfall_stays_owed() {
+        let mut stake = NftStake::default();
+        stake.settle(1_000).unwrap();
+
+        assert_eq!(stake.take_payout(600), 600);
+        assert_eq!(stake.owed, 400);
+        // The remainder is paid once the vault is refilled, on top of new earnings.
+        stake.settle(1_500).unwrap();
+        assert_eq!(stake.take_payout(10_000), 900);
+        assert_eq!(stake.owed, 0);
+    }
+
+    #[test]
+    fn test_boost_is_capped() {
+        assert_eq!(boosted_weight(1_000, 0, 5_000).unwrap(), 1_000);
+        assert_eq!(boosted_weight(1_000, 2_500, 5_000).unwrap(), 1_250);
+        assert_eq!(boosted_weight(1_000, 9_000, 5_000).unwrap(), 1_500);
+    }
+
+    #[test]
+    fn test_dropped_booster_earns_base_weight() {
+        let mut pool = StakePool {
+            schedule: EmissionSchedule { initial_rate: 100, budget: u64::MAX, ..Default::default() },
+            total_funded: u64::MAX,
+            boost_bps: [1_000, 2_000, 5_000, 10_000],
+            max_boost_bps: 10_000,
+            ..Default::default()
+        };
+        let mut user = UserStake::default();
+        stake_at(&mut pool, &mut user, 1_000, 0);
+        user.add_booster(Booster { nft_mint: Pubkey::new_unique(), tier: RarityTier::Epic }).unwrap();
+        user.reweight(&mut pool).unwrap();
+        assert_eq!(user.weight, 1_500);
+        assert!(user.add_booster(user.boosters[0]).is_err());
+
+        // The booster was lent out: it is stripped before the period is credited.
+        pool.update(10).unwrap();
+        user.retain_boosters(&[false; MAX_BOOSTERS]);
+        let weight = boosted_weight(user.amount, user.boost_bps(&pool), pool.max_boost_bps as u64).unwrap();
+        user.settle_at(pool.acc_reward_per_weight, weight).unwrap();
+        user.reweight(&mut pool).unwrap();
+
+        assert_eq!(user.booster_count, 0);
+        assert_eq!(user.weight, 1_000);
+        assert_eq!(pool.total_weight, 1_000);
+        // Alone in the pool, 1000 emitted, but only the unboosted 2/3 is credited.
+        assert_eq!(user.pending_rewards, 666);
+    }
+
+    #[test]
+    fn test_compound_bounty_split() {
+        assert_eq!(split_bounty(10_000, 50).unwrap(), (9_950, 50));
+        assert_eq!(split_bounty(199, 50).unwrap(), (199, 0));
+        assert_eq!(split_bounty(10_000, 10_000).unwrap(), (0, 10_000));
+    }
+
+    #[test]
+    fn test_emissions_integrate_across_halvings() {
+        let schedule = EmissionSchedule { start_time: 100, initial_rate: 1_000, halving_interval: 10, budget: 0 };
+
+        assert_eq!(schedule.rate_at(99), 0);
+        assert_eq!(schedule.rate_at(109), 1_000);
+        assert_eq!(schedule.rate_at(110), 500);
+        // Before start, then 10s at 1000, 10s at 500, 5s at 250.
+        assert_eq!(schedule.emitted_between(50, 125).unwrap(), 10_000 + 5_000 + 1_250);
+        // The tail converges instead of looping forever.
+        assert_eq!(schedule.emitted_between(100, i64::MAX).unwrap(), 19_940);
+    }
+
+    #[test]
+    fn test_emissions_never_exceed_budget() {
+        let mut pool = StakePool {
+            schedule: EmissionSchedule { initial_rate: 100, budget: 5_000, ..Default::default() },
+            total_funded: 3_000,
+            ..Default::default()
+        };
+        let mut user = UserStake::default();
+        stake_at(&mut pool, &mut user, 1_000, 0);
+
+        // Only 3000 has been funded so far.
+        pool.update(100).unwrap();
+        user.settle(pool.acc_reward_per_weight).unwrap();
+        assert_eq!(user.pending_rewards, 3_000);
+
+        // Topping up past the budget still stops at the budget.
+        pool.total_funded = 10_000;
+        pool.update(200).unwrap();
+        user.settle(pool.acc_reward_per_weight).unwrap();
+        assert_eq!(pool.total_emitted, 5_000);
+        assert_eq!(user.pending_rewards, 5_000);
+    }
+
+    #[test]
+    fn test_early_exit_penalty_decays_linearly() {
+        let day = 24 * 60 * 60;
+        // 20% max penalty, a quarter of it burned.
+        assert_eq!(early_exit_penalty(10_000, 30 * day, 30 * day, 2_000, 2_500).unwrap(), (500, 1_500));
+        assert_eq!(early_exit_penalty(10_000, 15 * day, 30 * day, 2_000, 2_500).unwrap(), (250, 750));
+        assert_eq!(early_exit_penalty(10_000, 0, 30 * day, 2_000, 2_500).unwrap(), (0, 0));
+        // A lock extended past its original length never charges more than the max.
+        assert_eq!(early_exit_penalty(10_000, 60 * day, 30 * day, 2_000, 10_000).unwrap(), (2_000, 0));
+    }
+
+    #[test]
+    fn test_stake_history_lookup() {
+        let mut history = StakeHistory { owner: Pubkey::new_unique(), ..Default::default() };
+        history.record(10, 100).unwrap();
+        history.record(20, 300).unwrap();
+        history.record(20, 250).unwrap();
+        history.record(30, 0).unwrap();
+
+        assert_eq!(history.len, 3);
+        assert_eq!(history.weight_at(9), 0);
+        assert_eq!(history.weight_at(10), 100);
+        assert_eq!(history.weight_at(25), 250);
+        assert_eq!(history.weight_at(1_000), 0);
+        assert!(history.record(29, 1).is_err());
+    }
+
+    #[test]
+    fn test_stake_history_drops_only_stale_checkpoints() {
+        let mut history = StakeHistory { owner: Pubkey::new_unique(), ..Default::default() };
+        history.record(0, 100).unwrap();
+        history.record(10, 200).unwrap();
+        history.record(20, 300).unwrap();
+
+        // Slots 0 and 10 are superseded before the window; slot 20 still answers its edge.
+        let now = 20 + HISTORY_RETENTION_SLOTS + 5;
+        history.record(now, 400).unwrap();
+        assert_eq!(history.active(), &[Checkpoint { slot: 20, weight: 300 }, Checkpoint { slot: now, weight: 400 }]);
+        assert_eq!(history.weight_at(now - HISTORY_RETENTION_SLOTS), 300);
+
+        // The pool history is only read one slot back, so it never fills.
+        let mut pool_history = StakeHistory::default();
+        for slot in 0..10 * MAX_CHECKPOINTS as u64 {
+            pool_history.record(slot, slot).unwrap();
+        }
+        assert_eq!(pool_history.weight_at(10 * MAX_CHECKPOINTS as u64 - 2), 10 * MAX_CHECKPOINTS as u64 - 2);
+    }
+
+    #[test]
+    fn test_stake_history_flood_keeps_snapshot_weight() {
+        let mut voter = StakeHistory { owner: Pubkey::new_unique(), power: 1_000, ..Default::default() };
+        voter.checkpoint(100).unwrap();
+        let snapshot = 150;
+
+        // A delegator churns 1 unit of power in and out every slot after the snapshot.
+        let mut slot = 200;
+        let flooded = loop {
+            voter.delegated_power = slot % 2;
+            if let Err(e) = voter.checkpoint(slot) {
+                break e;
+            }
+            slot += 1;
+        };
+        assert_eq!(flooded, ErrorCode::HistoryFull.into());
+        assert_eq!(voter.weight_at(snapshot), 1_000);
+
+        // Room returns once the flood falls out of the window, still without touching it.
+        voter.checkpoint(slot + HISTORY_RETENTION_SLOTS).unwrap();
+        assert_eq!(voter.weight_at(slot - 1), (slot - 1) % 2 + 1_000);
+    }
+
+    #[test]
+    fn test_voting_power_squares_heritage_multiplier() {
+        assert_eq!(voting_power(1_000, 0).unwrap(), 1_000);
+        // 150 days -> 1.5x -> 2.25x power, matching calculateVotingPower(1000, 1.5)
+        assert_eq!(voting_power(1_000, 5 * HERITAGE_MONTH).unwrap(), 2_250);
+        // Capped at 2.5x -> 6.25x power
+        assert_eq!(voting_power(1_000, 100 * HERITAGE_MONTH).unwrap(), 6_250);
+        assert_eq!(voting_power(1_000, -10).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_refresh_tracks_heritage_age() {
+        let owner = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut user_stake = UserStake { owner, pool, amount: 1_000, voting_power: 1_000, ..Default::default() };
+        let mut user_history = StakeHistory { owner, pool, power: 1_000, ..Default::default() };
+        let mut pool_history = StakeHistory { pool, power: 1_000, ..Default::default() };
+        user_history.checkpoint(1).unwrap();
+        let mut clock = Clock { slot: 2, unix_timestamp: 5 * HERITAGE_MONTH, ..Default::default() };
+
+        // Five months in, with no stake moved, the power is still the day-one value.
+        assert!(!user_history.refresh_due(clock.slot));
+        clock.slot += REFRESH_INTERVAL_SLOTS;
+        assert!(user_history.refresh_due(clock.slot));
+        sync_voting_power(&mut user_stake, &clock, &mut user_history, &mut pool_history, None, None).unwrap();
+
+        assert_eq!(user_history.weight_at(clock.slot), 2_250);
+        assert_eq!(user_history.weight_at(1), 1_000);
+        assert_eq!(pool_history.weight_at(clock.slot), 2_250);
+        assert!(!user_history.refresh_due(clock.slot + 1));
+    }
+
+    #[test]
+    fn test_delegated_power_follows_position() {
+        let delegator = Pubkey::new_unique();
+        let delegatee = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut user_stake = UserStake { owner: delegator, pool, amount: 1_000, delegatee, ..Default::default() };
+        let mut delegation = Delegation { delegator, pool, delegatee, ..Default::default() };
+        let mut user_history = StakeHistory { owner: delegator, pool, ..Default::default() };
+        let mut delegatee_history = StakeHistory { owner: delegatee, pool, power: 500, ..Default::default() };
+        let mut pool_history = StakeHistory { pool, power: 500, ..Default::default() };
+        let mut clock = Clock { slot: 1, unix_timestamp: 5 * HERITAGE_MONTH, ..Default::default() };
+
+        sync_voting_power(
+            &mut user_stake,
+            &clock,
+            &mut user_history,
+            &mut pool_history,
+            Some(&mut delegation),
+            Some(&mut delegatee_history),
+        )
+        .unwrap();
+        assert_eq!(delegation.power, 2_250);
+        assert_eq!(delegatee_history.weight_at(1), 2_750);
+        assert_eq!(user_history.weight_at(1), 0);
+        assert_eq!(pool_history.weight_at(1), 2_750);
+
+        // Unstaking half later shrinks the delegatee's power, not the delegator's.
+        user_stake.amount = 500;
+        clock.slot = 2;
+        sync_voting_power(
+            &mut user_stake,
+            &clock,
+            &mut user_history,
+            &mut pool_history,
+            Some(&mut delegation),
+            Some(&mut delegatee_history),
+        )
+        .unwrap();
+        assert_eq!(delegatee_history.weight_at(2), 500 + 1_125);
+        assert_eq!(delegatee_history.weight_at(1), 2_750);
+        assert_eq!(pool_history.weight_at(2), 1_625);
+
+        // A delegated position cannot be updated without its delegation accounts.
+        assert!(sync_voting_power(&mut user_stake, &clock, &mut user_history, &mut pool_history, None, None).is_err());
+    }
+
+    #[test]
+    fn test_slash_limit_per_epoch() {
+        let mut pool = StakePool { total_staked: 100_000, max_slash_bps_per_epoch: 1_000, ..Default::default() };
+
+        pool.charge_slash(7, 6_000).unwrap();
+        pool.total_staked -= 6_000;
+        // The limit stays at 10% of the epoch's opening stake, not of what is left.
+        pool.charge_slash(7, 4_000).unwrap();
+        pool.total_staked -= 4_000;
+        assert!(pool.charge_slash(7, 1).is_err());
+
+        // A new epoch resets the allowance against the reduced stake.
+        assert!(pool.charge_slash(8, 9_001).is_err());
+        pool.charge_slash(8, 9_000).unwrap();
+        assert_eq!(pool.slash_epoch_base, 90_000);
+    }
+
+    #[test]
+    fn test_emergency_release_skips_settlement() {
+        let mut pool = StakePool::default();
+        let mut a = UserStake::default();
+        let mut b = UserStake::default();
+        stake_at(&mut pool, &mut a, 1_000, 0);
+        stake_at(&mut pool, &mut b, 3_000, 0);
+        a.pending_rewards = 50;
+        pool.acc_reward_per_weight = 7 * REWARD_PRECISION;
+
+        assert_eq!(a.release_all(&mut pool).unwrap(), 1_000);
+        assert_eq!((a.amount, a.weight, a.pending_rewards), (0, 0, 50));
+        assert_eq!((pool.total_staked, pool.total_weight), (3_000, 3_000));
+
+        // Restaking later earns nothing for the skipped period.
+        a.settle(pool.acc_reward_per_weight).unwrap();
+        assert_eq!(a.pending_rewards, 50);
+    }
+
+    #[test]
+    fn test_heritage_milestones() {
+        let start = 1_700_000_000;
+        assert!(!heritage_milestone_reached(start, start + 14 * 86_400 - 1, 0).unwrap());
+        assert!(heritage_milestone_reached(start, start + 14 * 86_400, 0).unwrap());
+        assert!(heritage_milestone_reached(start, start + 100 * 86_400, 2).unwrap());
+        assert!(!heritage_milestone_reached(start, start + 100 * 86_400, 3).unwrap());
+        assert!(heritage_milestone_reached(start, start + 365 * 86_400, 4).is_err());
+        assert_eq!(heritage_badge_name(HERITAGE_MILESTONE_DAYS[1]), "GRIT Heritage: 30 Days");
+    }
+
+    #[test]
+    fn test_migrate_v0_stake() {
+        // A v0 account as deployed: discriminator, owner, amount, start_time.
+        let owner = Pubkey::new_unique();
+        let mut data = UserStake::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&4_200u64.to_le_bytes());
+        data.extend_from_slice(&1_650_000_000i64.to_le_bytes());
+        assert_eq!(data.len(), 56);
+
+        let legacy = read_v0_stake(&data).unwrap();
+        assert_eq!((legacy.owner, legacy.amount, legacy.start_time), (owner, 4_200, 1_650_000_000));
+
+        // Into an empty position, the principal and Heritage age carry over.
+        let mut pool = StakePool { total_staked: 1_000, total_weight: 1_000, ..Default::default() };
+        let mut position = UserStake::default();
+        position.absorb_v0(&legacy, &mut pool).unwrap();
+        assert_eq!((position.amount, position.weight, position.start_time), (4_200, 4_200, 1_650_000_000));
+        assert_eq!((pool.total_staked, pool.total_weight), (5_200, 5_200));
+
+        // Into an existing position, the earlier start time wins.
+        let mut position = UserStake { amount: 800, weight: 800, start_time: 1_700_000_000, ..Default::default() };
+        position.absorb_v0(&legacy, &mut pool).unwrap();
+        assert_eq!((position.amount, position.start_time), (5_000, 1_650_000_000));
+
+        // Current-layout accounts and other account types are rejected.
+        let mut account = vec![0u8; UserStake::LEN];
+        position.try_serialize(&mut &mut account[..]).unwrap();
+        assert!(read_v0_stake(&account).is_err());
+        let mut foreign = data.clone();
+        foreign[..8].copy_from_slice(&StakePool::DISCRIMINATOR);
+        assert!(read_v0_stake(&foreign).is_err());
+    }
+
+    #[test]
+    fn test_ascesis_record_first_burn() {
+        let mut record = AscesisRecord::default();
+        record.record_burn(100, 1_000).unwrap();
+        record.record_burn(300, 2_000).unwrap();
+
+        assert_eq!(record.initial_burn, 100);
+        assert_eq!(record.total_burned, 400);
+        assert_eq!(record.burn_count, 2);
+        assert_eq!(record.first_burn_time, 1_000);
+        assert_eq!(record.last_burn_time, 2_000);
+        assert_eq!(
+            record.recent_scars(),
+            vec![Scar { amount: 100, timestamp: 1_000 }, Scar { amount: 300, timestamp: 2_000 }]
+        );
+    }
+
+    #[test]
+    fn test_ascesis_scar_ring_wraps() {
+        let mut record = AscesisRecord::default();
+        let burns = MAX_SCARS as u64 + 3;
+        for i in 0..burns {
+            record.record_burn(i + 1, i as i64).unwrap();
+        }
+
+        // Totals keep the full history even after old scars are overwritten.
+        assert_eq!(record.initial_burn, 1);
+        assert_eq!(record.burn_count, burns);
+        assert_eq!(record.total_burned, burns * (burns + 1) / 2);
+
+        let scars = record.recent_scars();
+        assert_eq!(scars.len(), MAX_SCARS);
+        assert_eq!(scars.first().unwrap().amount, 4);
+        assert_eq!(scars.last().unwrap().amount, burns);
+    }
+
+    #[test]
+    fn test_ring_burn_requirements() {
+        let ring = AscesisRing { index: 2, required_burns: [1_000, 0, 50], min_burn_count: 3, bump: 0 };
+        assert_eq!(ring.burn_required(1, 2, 3, TokenType::Grit).unwrap(), 1_000);
+        assert_eq!(ring.burn_required(1, 2, 3, TokenType::Chi).unwrap(), 50);
+
+        // Rings cannot be skipped or repeated.
+        assert!(ring.burn_required(0, 2, 3, TokenType::Grit).is_err());
+        assert!(ring.burn_required(2, 2, 3, TokenType::Grit).is_err());
+        // Too few prior burns of the paying mint.
+        assert!(ring.burn_required(1, 2, 2, TokenType::Grit).is_err());
+        // A zero requirement means the ring does not accept that token.
+        assert!(ring.burn_required(1, 2, 3, TokenType::Moxy).is_err());
+    }
 }
@@ -9,7 +9,7 @@ export interface Proposal {
         amplify: number; // Yes votes (weighted)
         dampen: number;  // No votes (weighted)
     };
-    status: "Active" | "Passed" | "Rejected" | "Executed";
+    status: "Draft" | "Active" | "Passed" | "Rejected" | "Executed" | "Cancelled" | "Vetoed" | "Tallying";
     domain: keyof typeof DOMAINS;
 }
 
@@ -36,9 +36,11 @@ export function calculateVotingPower(stakedAmount: number, multiplier: number):
     return Math.floor(stakedAmount * Math.pow(multiplier, 2));
 }
 
+/**
+ * Outcomes are decided on-chain by `finalize_proposal` (quorum and pass threshold),
+ * so a proposal past its deadline stays open here until it has been finalized.
+ */
 export function getProposalStatus(proposal: Proposal): string {
-    if (Date.now() > proposal.deadline) {
-        return proposal.resonance.amplify > proposal.resonance.dampen ? "Passed" : "Rejected";
-    }
-    return "Active";
+    if (proposal.status !== "Active") return proposal.status;
+    return Date.now() > proposal.deadline ? "Awaiting Finalization" : "Active";
 }
@@ -1,4 +1,4 @@
-import { Program, AnchorProvider, Idl, setProvider } from "@coral-xyz/anchor";
+import { Program, AnchorProvider, Idl, setProvider, BN } from "@coral-xyz/anchor";
 import { Connection, PublicKey } from "@solana/web3.js";
 import gritBondingIdl from "./idl/grit_bonding.json";
 import gritStakingIdl from "./idl/grit_staking.json";
@@ -35,6 +35,29 @@ export const getGovProgram = (provider: AnchorProvider) => {
     return new Program(normalizeIdl(gritGovIdl), GOV_PROGRAM_ID, provider);
 };
 
+export const GRIT_MINT = new PublicKey(process.env.NEXT_PUBLIC_GRIT_MINT || "CS8ZQMdJ5t5hNuM51LXJBU4zBysZWAkFj9oJ6MwtnHsS");
+
+// Order of the on-chain `Domain` enum; a domain's index is its `domain_config` seed.
+export const GOV_DOMAINS = ["sovereign", "ascesis", "heritage", "market"] as const;
+
+const pda = (seeds: Buffer[], programId: PublicKey) => PublicKey.findProgramAddressSync(seeds, programId)[0];
+
+// Governance addresses, derived with the same seeds as grit-gov and grit-staking.
+export const govPdas = {
+    realm: (governingMint: PublicKey) => pda([Buffer.from("realm"), governingMint.toBuffer()], GOV_PROGRAM_ID),
+    proposal: (realm: PublicKey, id: BN) =>
+        pda([Buffer.from("proposal"), realm.toBuffer(), id.toArrayLike(Buffer, "le", 8)], GOV_PROGRAM_ID),
+    domainConfig: (realm: PublicKey, domain: number) =>
+        pda([Buffer.from("domain"), realm.toBuffer(), Buffer.from([domain])], GOV_PROGRAM_ID),
+    depositVault: (realm: PublicKey) => pda([Buffer.from("deposit_vault"), realm.toBuffer()], GOV_PROGRAM_ID),
+    voteRecord: (proposal: PublicKey, voter: PublicKey) =>
+        pda([Buffer.from("vote"), proposal.toBuffer(), voter.toBuffer()], GOV_PROGRAM_ID),
+    stakePool: (stakeMint: PublicKey) => pda([Buffer.from("pool"), stakeMint.toBuffer()], STAKING_PROGRAM_ID),
+    // Pool-wide history when `owner` is omitted.
+    history: (stakePool: PublicKey, owner?: PublicKey) =>
+        pda([Buffer.from("history"), stakePool.toBuffer(), ...(owner ? [owner.toBuffer()] : [])], STAKING_PROGRAM_ID),
+};
+
 // Treasury
 import gritTreasuryIdl from "./idl/grit_treasury.json";
 const TREASURY_PROGRAM_ID = new PublicKey("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");